@@ -0,0 +1,18 @@
+use anyhow::Result;
+use std::time::Instant;
+
+/// Run `f` the given number of times and print the mean time per iteration
+pub fn bench<T, F: FnMut() -> Result<T>>(name: &str, iterations: u32, mut f: F) -> Result<()> {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f()?;
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:<24} {:>10.3?} / iteration ({} iterations)",
+        name,
+        elapsed / iterations,
+        iterations
+    );
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use anyhow::{anyhow, Result};
+
+use aoc_2019::intcode::{Engine, Interpreter};
+use common::bench;
+
+mod common;
+
+fn boost(computer: &Interpreter, engine: Engine) -> Result<isize> {
+    let mut computer = computer.clone();
+    computer.set_engine(engine);
+    computer
+        .run_with(vec![2])
+        .last()
+        .ok_or_else(|| anyhow!("No output"))?
+}
+
+fn main() -> Result<()> {
+    let day9 = Interpreter::from_path("data/day9.txt")?;
+
+    bench("day9 boost (standard)", 5, || {
+        boost(&day9, Engine::Standard)
+    })?;
+    bench("day9 boost (decoded)", 5, || boost(&day9, Engine::Decoded))?;
+
+    Ok(())
+}
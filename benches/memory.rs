@@ -0,0 +1,75 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fs::read_to_string;
+
+use aoc_2019::intcode::{Interpreter, Memory, PagedMemory, State};
+use common::bench;
+
+mod common;
+
+fn load<M: Memory<Word = isize>>(
+    path: &str,
+    memory: fn(Vec<isize>) -> M,
+) -> Result<Interpreter<M>> {
+    let program = read_to_string(path)?
+        .trim_end()
+        .split(',')
+        .map(|x| x.parse())
+        .collect::<Result<Vec<isize>, _>>()?;
+    Ok(Interpreter::new(memory(program)))
+}
+
+fn hash_map(program: Vec<isize>) -> HashMap<usize, isize> {
+    program.into_iter().enumerate().collect()
+}
+
+fn paged(program: Vec<isize>) -> PagedMemory {
+    program.into_iter().collect()
+}
+
+fn noun_verb_search<M: Memory<Word = isize>>(computer: &Interpreter<M>) -> Result<isize> {
+    for noun in 0..=99 {
+        for verb in 0..=99 {
+            let mut fork = computer.clone();
+            fork.put(1, noun);
+            fork.put(2, verb);
+            if let State::Halt(memory) = fork.run()? {
+                if memory.get(0) == 19690720 {
+                    return Ok(100 * noun + verb);
+                }
+            }
+        }
+    }
+    Err(anyhow!("No noun and verb found"))
+}
+
+fn boost<M: Memory<Word = isize>>(computer: &Interpreter<M>) -> Result<isize> {
+    let mut output = None;
+    let mut state = computer.clone().run()?;
+    loop {
+        state = match state {
+            State::Input(c) => c.resume(2)?,
+            State::Output(c) => {
+                output = Some(c.get());
+                c.resume()?
+            }
+            State::Limit(c) => return Err(anyhow!("{}", c.limit())),
+            State::Halt(_) => break,
+        };
+    }
+    output.ok_or_else(|| anyhow!("No output"))
+}
+
+fn main() -> Result<()> {
+    let day2_map = load("data/day2.txt", hash_map)?;
+    let day2_paged = load("data/day2.txt", paged)?;
+    let day9_map = load("data/day9.txt", hash_map)?;
+    let day9_paged = load("data/day9.txt", paged)?;
+
+    bench("day2 search (HashMap)", 10, || noun_verb_search(&day2_map))?;
+    bench("day2 search (paged)", 10, || noun_verb_search(&day2_paged))?;
+    bench("day9 boost (HashMap)", 3, || boost(&day9_map))?;
+    bench("day9 boost (paged)", 3, || boost(&day9_paged))?;
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
+use std::cmp::Ordering;
 use std::ops::{Add, Sub};
 
 use self::Direction::*;
+use crate::math::gcd;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct Coord {
@@ -8,6 +10,13 @@ pub struct Coord {
     pub y: isize,
 }
 
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Coord3 {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum Direction {
     Up(usize),
@@ -16,6 +25,15 @@ pub enum Direction {
     Left(usize),
 }
 
+/// Facing of something that can turn, with north pointing up
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Heading {
+    North,
+    East,
+    South,
+    West,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Path {
     dirs: Vec<Direction>,
@@ -47,6 +65,29 @@ impl Coord {
         Self::distance(*self, Self::origin())
     }
 
+    /// Shortest offset with the same direction, like (2, -4) to (1, -2)
+    pub fn reduce(&self) -> Self {
+        match gcd(self.x, self.y) {
+            0 => *self,
+            n => Coord::new(self.x / n, self.y / n),
+        }
+    }
+
+    /// Compare offsets by their angle clockwise from straight up. Only integer
+    /// arithmetic is used so offsets in the same direction are always equal.
+    /// The origin has no direction and comes before every other offset.
+    pub fn angle_cmp(&self, other: &Self) -> Ordering {
+        // Up and everything to its right comes before the rest
+        let half = |c: &Coord| match (c.x, c.y) {
+            (0, 0) => 0,
+            (x, y) if x > 0 || (x == 0 && y > 0) => 1,
+            _ => 2,
+        };
+        half(self)
+            .cmp(&half(other))
+            .then_with(|| (self.x * other.y - self.y * other.x).cmp(&0))
+    }
+
     pub fn offset(&self, dir: Direction) -> Self {
         *self
             + match dir {
@@ -98,6 +139,79 @@ impl Direction {
     }
 }
 
+impl Coord3 {
+    pub fn new(x: isize, y: isize, z: isize) -> Self {
+        Coord3 { x, y, z }
+    }
+
+    pub fn origin() -> Self {
+        Coord3 { x: 0, y: 0, z: 0 }
+    }
+
+    pub fn distance_from_origin(&self) -> usize {
+        (self.x.abs() + self.y.abs() + self.z.abs()) as usize
+    }
+
+    pub fn signum(&self) -> Self {
+        Coord3::new(self.x.signum(), self.y.signum(), self.z.signum())
+    }
+
+    pub fn axes(&self) -> [isize; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl Add for Coord3 {
+    type Output = Coord3;
+    fn add(self, rhs: Self) -> Self::Output {
+        Coord3 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl Sub for Coord3 {
+    type Output = Coord3;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Coord3 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl Heading {
+    pub fn turn_left(self) -> Self {
+        match self {
+            Heading::North => Heading::West,
+            Heading::East => Heading::North,
+            Heading::South => Heading::East,
+            Heading::West => Heading::South,
+        }
+    }
+
+    pub fn turn_right(self) -> Self {
+        match self {
+            Heading::North => Heading::East,
+            Heading::East => Heading::South,
+            Heading::South => Heading::West,
+            Heading::West => Heading::North,
+        }
+    }
+
+    pub fn direction(self, len: usize) -> Direction {
+        match self {
+            Heading::North => Up(len),
+            Heading::East => Right(len),
+            Heading::South => Down(len),
+            Heading::West => Left(len),
+        }
+    }
+}
+
 impl Path {
     pub fn new(dirs: Vec<Direction>) -> Self {
         Self { dirs }
@@ -194,6 +308,75 @@ mod tests {
         assert_eq!(Coord::origin().offset(Left(100)), Coord::new(-100, 0));
     }
 
+    #[test]
+    fn test_coord3() {
+        let a = Coord3::new(1, -2, 3);
+        let b = Coord3::new(4, -2, -1);
+        assert_eq!(a + b, Coord3::new(5, -4, 2));
+        assert_eq!(a - b, Coord3::new(-3, 0, 4));
+        assert_eq!((a - b).signum(), Coord3::new(-1, 0, 1));
+        assert_eq!(a.distance_from_origin(), 6);
+        assert_eq!(b.axes(), [4, -2, -1]);
+    }
+
+    #[test]
+    fn test_reduce() {
+        assert_eq!(Coord::new(2, -4).reduce(), Coord::new(1, -2));
+        assert_eq!(Coord::new(0, 7).reduce(), Coord::new(0, 1));
+        assert_eq!(Coord::new(3, 5).reduce(), Coord::new(3, 5));
+        assert_eq!(Coord::origin().reduce(), Coord::origin());
+    }
+
+    #[test]
+    fn test_angle_cmp() {
+        let mut offsets = vec![
+            Coord::new(-1, 1),
+            Coord::new(0, -3),
+            Coord::new(1, 0),
+            Coord::new(-2, 0),
+            Coord::new(2, 1),
+            Coord::new(0, 1),
+            Coord::new(-1, -1),
+            Coord::new(1, -1),
+        ];
+        offsets.sort_by(Coord::angle_cmp);
+        assert_eq!(
+            offsets,
+            vec![
+                Coord::new(0, 1),
+                Coord::new(2, 1),
+                Coord::new(1, 0),
+                Coord::new(1, -1),
+                Coord::new(0, -3),
+                Coord::new(-1, -1),
+                Coord::new(-2, 0),
+                Coord::new(-1, 1),
+            ]
+        );
+        assert_eq!(
+            Coord::new(2, 4).angle_cmp(&Coord::new(1, 2)),
+            Ordering::Equal
+        );
+
+        let origin = Coord::origin();
+        assert_eq!(origin.angle_cmp(&origin), Ordering::Equal);
+        for offset in &offsets {
+            assert_eq!(origin.angle_cmp(offset), Ordering::Less);
+            assert_eq!(offset.angle_cmp(&origin), Ordering::Greater);
+        }
+    }
+
+    #[test]
+    fn test_heading() {
+        assert_eq!(Heading::North.turn_left(), Heading::West);
+        assert_eq!(Heading::West.turn_right(), Heading::North);
+        assert_eq!(Heading::North.turn_right().turn_right(), Heading::South);
+        assert_eq!(
+            Coord::origin().offset(Heading::East.turn_left().direction(2)),
+            Coord::new(0, 2)
+        );
+    }
+
     #[test]
     fn test_walk() {
         assert_eq!(Path::from(Up(1000)).walk().count(), 1000);
@@ -4,12 +4,7 @@ use std::io::prelude::*;
 use std::io::BufReader;
 
 fn get_fuel_req(mass: usize) -> usize {
-    let mass = mass / 3;
-    if mass < 2 {
-        0
-    } else {
-        mass - 2
-    }
+    (mass / 3).saturating_sub(2)
 }
 
 fn get_fuel_load_req(mass: usize) -> usize {
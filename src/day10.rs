@@ -0,0 +1,172 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+
+use crate::coord::Coord;
+
+/// Asteroid positions with x growing to the right and y growing downwards
+pub fn parse(map: &str) -> Result<HashSet<Coord>> {
+    let mut asteroids = HashSet::new();
+    for (y, line) in map.lines().enumerate() {
+        for (x, c) in line.trim().chars().enumerate() {
+            match c {
+                '#' => {
+                    asteroids.insert(Coord::new(x as isize, y as isize));
+                }
+                '.' => {}
+                _ => return Err(anyhow!("Unexpected {:?} at line {}", c, y + 1)),
+            }
+        }
+    }
+    Ok(asteroids)
+}
+
+/// Offset from `station` to `asteroid` with y growing upwards, which is the
+/// orientation `Coord::angle_cmp` expects
+fn offset(station: Coord, asteroid: Coord) -> Coord {
+    let offset = asteroid - station;
+    Coord::new(offset.x, -offset.y)
+}
+
+pub fn visible(asteroids: &HashSet<Coord>, station: Coord) -> usize {
+    asteroids
+        .iter()
+        .filter(|&&asteroid| asteroid != station)
+        .map(|&asteroid| offset(station, asteroid).reduce())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+pub fn best_station(asteroids: &HashSet<Coord>) -> Option<(Coord, usize)> {
+    asteroids
+        .iter()
+        .map(|&station| (station, visible(asteroids, station)))
+        .max_by_key(|&(station, count)| (count, -station.y, -station.x))
+}
+
+/// Order in which a laser starting straight up and rotating clockwise
+/// vaporizes every other asteroid
+pub fn vaporization_order(asteroids: &HashSet<Coord>, station: Coord) -> Vec<Coord> {
+    let mut targets: Vec<_> = asteroids
+        .iter()
+        .filter(|&&asteroid| asteroid != station)
+        .map(|&asteroid| (offset(station, asteroid), asteroid))
+        .collect();
+    targets.sort_by(|(a, _), (b, _)| {
+        a.angle_cmp(b)
+            .then_with(|| a.distance_from_origin().cmp(&b.distance_from_origin()))
+    });
+
+    // Asteroids hidden behind others in the same direction are only hit on
+    // later rotations
+    let mut ranked = Vec::with_capacity(targets.len());
+    for (i, &(dir, asteroid)) in targets.iter().enumerate() {
+        let rotation = match i.checked_sub(1).map(|j| &ranked[j]) {
+            Some(&(rotation, prev, _)) if dir.angle_cmp(&prev).is_eq() => rotation + 1,
+            _ => 0,
+        };
+        ranked.push((rotation, dir, asteroid));
+    }
+    ranked.sort_by(|(a_rot, a, _), (b_rot, b, _)| a_rot.cmp(b_rot).then_with(|| a.angle_cmp(b)));
+    ranked
+        .into_iter()
+        .map(|(_, _, asteroid)| asteroid)
+        .collect()
+}
+
+pub fn main(args: &[String]) -> Result<(usize, Option<isize>)> {
+    if args.len() != 1 {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    let asteroids = parse(&std::fs::read_to_string(&args[0])?)?;
+    let (station, count) = best_station(&asteroids).ok_or_else(|| anyhow!("No asteroids found"))?;
+    let target = vaporization_order(&asteroids, station)
+        .get(199)
+        .cloned()
+        .ok_or_else(|| anyhow!("Fewer than 200 asteroids were vaporized"))?;
+    Ok((count, Some(target.x * 100 + target.y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = "
+        .#..#
+        .....
+        #####
+        ....#
+        ...##
+    ";
+
+    const LARGE: &str = "
+        .#..##.###...#######
+        ##.############..##.
+        .#.######.########.#
+        .###.#######.####.#.
+        #####.##.#.##.###.##
+        ..#####..#.#########
+        ####################
+        #.####....###.#.#.##
+        ##.#################
+        #####.##.###..####..
+        ..######..##.#######
+        ####.##.####...##..#
+        .#####..#.######.###
+        ##...#.##########...
+        #.##########.#######
+        .####.#.###.###.#.##
+        ....##.##.###..#####
+        .#.#.###########.###
+        #.#.#.#####.####.###
+        ###.##.####.##.#..##
+    ";
+
+    fn map(map: &str) -> Result<HashSet<Coord>> {
+        parse(map.trim())
+    }
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let asteroids = map(SMALL)?;
+        assert_eq!(asteroids.len(), 10);
+        assert!(asteroids.contains(&Coord::new(1, 0)));
+        assert!(asteroids.contains(&Coord::new(4, 3)));
+        assert!(parse(".#\n#x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_best_station() -> Result<()> {
+        let asteroids = map(SMALL)?;
+        assert_eq!(visible(&asteroids, Coord::new(1, 0)), 7);
+        assert_eq!(visible(&asteroids, Coord::new(4, 2)), 5);
+        assert_eq!(best_station(&asteroids), Some((Coord::new(3, 4), 8)));
+
+        assert_eq!(best_station(&map(LARGE)?), Some((Coord::new(11, 13), 210)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_vaporization_order() -> Result<()> {
+        let asteroids = map(LARGE)?;
+        let order = vaporization_order(&asteroids, Coord::new(11, 13));
+        assert_eq!(order.len(), 299);
+        for &(i, x, y) in &[
+            (1, 11, 12),
+            (2, 12, 1),
+            (3, 12, 2),
+            (10, 12, 8),
+            (20, 16, 0),
+            (50, 16, 9),
+            (100, 10, 16),
+            (199, 9, 6),
+            (200, 8, 2),
+            (201, 10, 9),
+            (299, 11, 1),
+        ] {
+            assert_eq!(order[i - 1], Coord::new(x, y), "asteroid {}", i);
+        }
+        Ok(())
+    }
+}
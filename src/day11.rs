@@ -0,0 +1,139 @@
+use anyhow::{anyhow, Result};
+
+use crate::coord::{Coord, Heading};
+use crate::grid::Canvas;
+use crate::intcode::{Interpreter, State};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Color {
+    Black,
+    White,
+}
+
+impl Color {
+    fn from_output(value: isize) -> Result<Self> {
+        match value {
+            0 => Ok(Color::Black),
+            1 => Ok(Color::White),
+            _ => Err(anyhow!("Invalid color {}", value)),
+        }
+    }
+}
+
+fn turn(heading: Heading, value: isize) -> Result<Heading> {
+    match value {
+        0 => Ok(heading.turn_left()),
+        1 => Ok(heading.turn_right()),
+        _ => Err(anyhow!("Invalid turn {}", value)),
+    }
+}
+
+/// Run the robot until it halts and return every panel it painted. The panel
+/// it starts on has the given color until painted.
+pub fn paint(computer: Interpreter, start: Color) -> Result<Canvas<Color>> {
+    let mut hull = Canvas::new();
+    let mut pos = Coord::origin();
+    let mut heading = Heading::North;
+    let mut color = None;
+
+    let mut state = computer.run()?;
+    loop {
+        state = match state {
+            State::Input(c) => {
+                let current = match hull.get(pos) {
+                    Some(&color) => color,
+                    None if pos == Coord::origin() => start,
+                    None => Color::Black,
+                };
+                c.resume(current as isize)?
+            }
+            State::Output(c) => {
+                match color.take() {
+                    None => color = Some(Color::from_output(c.get())?),
+                    Some(color) => {
+                        hull.set(pos, color);
+                        heading = turn(heading, c.get())?;
+                        pos = pos.offset(heading.direction(1));
+                    }
+                }
+                c.resume()?
+            }
+            State::Limit(c) => return Err(anyhow!("{}", c.limit())),
+            State::Halt(_) => return Ok(hull),
+        }
+    }
+}
+
+pub fn render(hull: &Canvas<Color>) -> String {
+    hull.render(|color| match color {
+        Some(Color::White) => '#',
+        _ => ' ',
+    })
+}
+
+pub fn main(args: &[String]) -> Result<(usize, Option<String>)> {
+    if args.len() != 1 {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    let computer = Interpreter::from_path(&args[0])?;
+    Ok((
+        paint(computer.clone(), Color::Black)?.len(),
+        Some(render(&paint(computer, Color::White)?)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    // Follows the example from the puzzle, except that the panel it returns to
+    // is painted the opposite of what the camera sees
+    const ROBOT: &str = "
+        in c
+        out #1
+        out #0
+        in c
+        out #0
+        out #0
+        in c
+        out #1
+        out #0
+        in c
+        out #1
+        out #0
+        in c
+        eq c, #0, c
+        out c
+        out #1
+        in c
+        out #1
+        out #0
+        in c
+        out #1
+        out #0
+        hlt
+        c: data 0
+    ";
+
+    #[test]
+    fn test_paint() -> Result<()> {
+        let hull = paint(assemble(ROBOT)?, Color::Black)?;
+        assert_eq!(hull.len(), 6);
+        assert_eq!(hull.get(Coord::origin()), Some(&Color::Black));
+        assert_eq!(render(&hull), "  #\n  #\n## ");
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_output() -> Result<()> {
+        assert!(paint(assemble("in c\nout #2\nhlt\nc: data 0")?, Color::Black).is_err());
+        assert!(paint(
+            assemble("in c\nout #1\nout #3\nhlt\nc: data 0")?,
+            Color::Black
+        )
+        .is_err());
+        Ok(())
+    }
+}
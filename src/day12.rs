@@ -0,0 +1,168 @@
+use anyhow::{anyhow, Result};
+
+use crate::coord::Coord3;
+use crate::math::{find_cycle, lcm};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Moon {
+    pub pos: Coord3,
+    pub vel: Coord3,
+}
+
+impl Moon {
+    pub fn new(pos: Coord3) -> Self {
+        Self {
+            pos,
+            vel: Coord3::origin(),
+        }
+    }
+
+    pub fn energy(&self) -> usize {
+        self.pos.distance_from_origin() * self.vel.distance_from_origin()
+    }
+}
+
+fn parse_moon(line: &str) -> Result<Moon> {
+    let inner = line
+        .trim()
+        .strip_prefix('<')
+        .and_then(|line| line.strip_suffix('>'))
+        .ok_or_else(|| anyhow!("Expected position in angle brackets: {}", line))?;
+
+    let mut axes = [0; 3];
+    let mut parts = inner.split(',');
+    for (axis, name) in axes.iter_mut().zip(&["x", "y", "z"]) {
+        let part = parts
+            .next()
+            .ok_or_else(|| anyhow!("Missing {} in {}", name, line))?;
+        *axis = match part.trim().split_once('=') {
+            Some((key, value)) if key == *name => value.parse()?,
+            _ => return Err(anyhow!("Expected {} in {}", name, line)),
+        };
+    }
+    if parts.next().is_some() {
+        return Err(anyhow!("Unexpected trailing value in {}", line));
+    }
+    Ok(Moon::new(Coord3::new(axes[0], axes[1], axes[2])))
+}
+
+pub fn parse(input: &str) -> Result<Vec<Moon>> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_moon)
+        .collect()
+}
+
+pub fn step(moons: &mut [Moon]) {
+    for i in 0..moons.len() {
+        let pos = moons[i].pos;
+        let pull = moons.iter().fold(Coord3::origin(), |pull, other| {
+            pull + (other.pos - pos).signum()
+        });
+        moons[i].vel = moons[i].vel + pull;
+    }
+    for moon in moons.iter_mut() {
+        moon.pos = moon.pos + moon.vel;
+    }
+}
+
+pub fn total_energy(moons: &[Moon], steps: usize) -> usize {
+    let mut moons = moons.to_vec();
+    for _ in 0..steps {
+        step(&mut moons);
+    }
+    moons.iter().map(Moon::energy).sum()
+}
+
+/// Advance the position and velocity of every moon along a single axis
+fn step_axis(axis: &[(isize, isize)]) -> Vec<(isize, isize)> {
+    axis.iter()
+        .map(|&(pos, vel)| {
+            let vel = vel
+                + axis
+                    .iter()
+                    .map(|&(other, _)| (other - pos).signum())
+                    .sum::<isize>();
+            (pos + vel, vel)
+        })
+        .collect()
+}
+
+/// Number of steps until the moons return to an earlier state. The axes don't
+/// affect each other so the period is the least common multiple of theirs.
+pub fn period(moons: &[Moon]) -> usize {
+    (0..3)
+        .map(|i| {
+            let axis: Vec<_> = moons
+                .iter()
+                .map(|moon| (moon.pos.axes()[i], moon.vel.axes()[i]))
+                .collect();
+            find_cycle(axis, |axis| step_axis(axis)).1
+        })
+        .fold(1, lcm)
+}
+
+pub fn main(args: &[String]) -> Result<(usize, Option<usize>)> {
+    if args.len() != 1 {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    let moons = parse(&std::fs::read_to_string(&args[0])?)?;
+    Ok((total_energy(&moons, 1000), Some(period(&moons))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST: &str = "
+        <x=-1, y=0, z=2>
+        <x=2, y=-10, z=-7>
+        <x=4, y=-8, z=8>
+        <x=3, y=5, z=-1>
+    ";
+
+    const SECOND: &str = "
+        <x=-8, y=-10, z=0>
+        <x=5, y=5, z=10>
+        <x=2, y=-7, z=3>
+        <x=9, y=-8, z=-3>
+    ";
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let moons = parse(FIRST)?;
+        assert_eq!(moons.len(), 4);
+        assert_eq!(moons[1], Moon::new(Coord3::new(2, -10, -7)));
+        assert!(parse("<x=1, y=2>").is_err());
+        assert!(parse("<x=1, z=2, y=3>").is_err());
+        assert!(parse("x=1, y=2, z=3").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_step() -> Result<()> {
+        let mut moons = parse(FIRST)?;
+        step(&mut moons);
+        assert_eq!(moons[0].pos, Coord3::new(2, -1, 1));
+        assert_eq!(moons[0].vel, Coord3::new(3, -1, -1));
+        assert_eq!(moons[3].pos, Coord3::new(2, 2, 0));
+        assert_eq!(moons[3].vel, Coord3::new(-1, -3, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_energy() -> Result<()> {
+        assert_eq!(total_energy(&parse(FIRST)?, 10), 179);
+        assert_eq!(total_energy(&parse(SECOND)?, 100), 1940);
+        Ok(())
+    }
+
+    #[test]
+    fn test_period() -> Result<()> {
+        assert_eq!(period(&parse(FIRST)?), 2772);
+        assert_eq!(period(&parse(SECOND)?), 4686774924);
+        Ok(())
+    }
+}
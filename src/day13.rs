@@ -0,0 +1,249 @@
+use anyhow::{anyhow, Result};
+use std::cmp::Ordering;
+use std::io::Write;
+
+use crate::coord::Coord;
+use crate::grid::Canvas;
+use crate::intcode::{Interpreter, State};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Tile {
+    Empty,
+    Wall,
+    Block,
+    Paddle,
+    Ball,
+}
+
+impl Tile {
+    fn from_id(id: isize) -> Result<Self> {
+        Ok(match id {
+            0 => Tile::Empty,
+            1 => Tile::Wall,
+            2 => Tile::Block,
+            3 => Tile::Paddle,
+            4 => Tile::Ball,
+            _ => return Err(anyhow!("Invalid tile id {}", id)),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game {
+    pub screen: Canvas<Tile>,
+    pub score: isize,
+    pub ball: Option<Coord>,
+    pub paddle: Option<Coord>,
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Self {
+            screen: Canvas::screen(),
+            score: 0,
+            ball: None,
+            paddle: None,
+        }
+    }
+
+    /// Apply one `x, y, value` triple written by the program
+    pub fn update(&mut self, x: isize, y: isize, value: isize) -> Result<()> {
+        if (x, y) == (-1, 0) {
+            self.score = value;
+            return Ok(());
+        }
+
+        let coord = Coord::new(x, y);
+        let tile = Tile::from_id(value)?;
+        match tile {
+            Tile::Ball => self.ball = Some(coord),
+            Tile::Paddle => self.paddle = Some(coord),
+            _ => {}
+        }
+        self.screen.set(coord, tile);
+        Ok(())
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.screen
+            .iter()
+            .filter(|&(_, &tile)| tile == Tile::Block)
+            .count()
+    }
+
+    /// Joystick position that keeps the paddle under the ball
+    pub fn joystick(&self) -> isize {
+        match (self.ball, self.paddle) {
+            (Some(ball), Some(paddle)) => match ball.x.cmp(&paddle.x) {
+                Ordering::Less => -1,
+                Ordering::Equal => 0,
+                Ordering::Greater => 1,
+            },
+            _ => 0,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let screen = self.screen.render(|tile| match tile {
+            Some(Tile::Wall) => '#',
+            Some(Tile::Block) => '=',
+            Some(Tile::Paddle) => '-',
+            Some(Tile::Ball) => 'o',
+            Some(Tile::Empty) | None => ' ',
+        });
+        format!("{}\nScore: {}", screen, self.score)
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn draw(frames: &mut dyn Write, game: &Game) -> Result<()> {
+    // Clear the terminal and move the cursor to the top left corner
+    writeln!(frames, "\x1b[2J\x1b[H{}", game.render())?;
+    frames.flush()?;
+    Ok(())
+}
+
+/// Run the game until it halts, moving the joystick towards the ball whenever
+/// input is requested. Each frame is drawn to `frames` before the joystick
+/// input is given.
+pub fn play(computer: Interpreter, mut frames: Option<&mut dyn Write>) -> Result<Game> {
+    let mut game = Game::new();
+    let mut triple = Vec::with_capacity(3);
+
+    let mut state = computer.run()?;
+    loop {
+        state = match state {
+            State::Input(c) => {
+                if let Some(frames) = frames.as_mut() {
+                    draw(frames, &game)?;
+                }
+                c.resume(game.joystick())?
+            }
+            State::Output(c) => {
+                triple.push(c.get());
+                if let [x, y, value] = triple[..] {
+                    game.update(x, y, value)?;
+                    triple.clear();
+                }
+                c.resume()?
+            }
+            State::Limit(c) => return Err(anyhow!("{}", c.limit())),
+            State::Halt(_) => break,
+        }
+    }
+
+    if !triple.is_empty() {
+        return Err(anyhow!("Program halted in the middle of a tile"));
+    }
+    if let Some(frames) = frames {
+        draw(frames, &game)?;
+    }
+    Ok(game)
+}
+
+pub fn main(args: &[String]) -> Result<(usize, Option<isize>)> {
+    let show = match args {
+        [_] => false,
+        [_, mode] if mode == "render" => true,
+        _ => return Err(anyhow!("Expected path to input and optionally render")),
+    };
+
+    let computer = Interpreter::from_path(&args[0])?;
+    let blocks = play(computer.clone(), None)?.blocks();
+
+    let mut free_play = computer;
+    free_play.put(0, 2);
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    let frames: Option<&mut dyn Write> = if show { Some(&mut stdout) } else { None };
+    Ok((blocks, Some(play(free_play, frames)?.score)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    // Draws a small screen and then reports the paddle position after two
+    // joystick moves as the score
+    const GAME: &str = "
+              out #0
+              out #0
+              out #1
+              out #1
+              out #0
+              out #2
+              out #2
+              out #0
+              out #2
+              out #2
+              out #3
+              out #3
+              out #4
+              out #2
+              out #4
+              in j
+              add px, j, px
+              out px
+              out #3
+              out #3
+              out #1
+              out #0
+              out #0
+              out #3
+              out #2
+              out #4
+              in j
+              mul px, #10, s
+              add s, j, s
+              out #-1
+              out #0
+              out s
+              hlt
+        px:   data 2
+        j:    data 0
+        s:    data 0
+    ";
+
+    #[test]
+    fn test_update() -> Result<()> {
+        let mut game = Game::new();
+        game.update(1, 2, 3)?;
+        game.update(4, 0, 4)?;
+        game.update(0, 0, 2)?;
+        game.update(-1, 0, 12)?;
+        assert_eq!(game.paddle, Some(Coord::new(1, 2)));
+        assert_eq!(game.ball, Some(Coord::new(4, 0)));
+        assert_eq!(game.score, 12);
+        assert_eq!(game.blocks(), 1);
+        assert_eq!(game.joystick(), 1);
+        assert_eq!(game.render(), "=   o\n     \n -   \nScore: 12");
+        assert!(game.update(0, 0, 5).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_play() -> Result<()> {
+        let game = play(assemble(GAME)?, None)?;
+        assert_eq!(game.score, 30);
+        assert_eq!(game.blocks(), 1);
+        assert_eq!(game.paddle, Some(Coord::new(3, 3)));
+        assert_eq!(game.ball, Some(Coord::new(3, 2)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_frames() -> Result<()> {
+        let mut frames = Vec::new();
+        play(assemble(GAME)?, Some(&mut frames))?;
+        let frames = String::from_utf8(frames)?;
+        assert_eq!(frames.matches("\x1b[2J").count(), 3);
+        assert!(frames.ends_with("Score: 30\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reaction {
+    pub quantity: u64,
+    pub inputs: Vec<(String, u64)>,
+}
+
+/// Reactions by the chemical they produce, along with an order where every
+/// chemical comes before the chemicals used to make it
+#[derive(Clone, Debug)]
+pub struct Nanofactory {
+    reactions: HashMap<String, Reaction>,
+    order: Vec<String>,
+}
+
+fn parse_chemical(chemical: &str) -> Result<(String, u64)> {
+    let mut parts = chemical.split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(quantity), Some(name), None) => match quantity.parse()? {
+            0 => Err(anyhow!("Quantity of {} must not be zero", name)),
+            quantity => Ok((name.to_owned(), quantity)),
+        },
+        _ => Err(anyhow!(
+            "Expected quantity and chemical, got {:?}",
+            chemical
+        )),
+    }
+}
+
+fn parse_reaction(line: &str) -> Result<(String, Reaction)> {
+    let mut sides = line.split("=>");
+    let (inputs, output) = match (sides.next(), sides.next(), sides.next()) {
+        (Some(inputs), Some(output), None) => (inputs, output),
+        _ => return Err(anyhow!("Expected a single =>")),
+    };
+    let (name, quantity) = parse_chemical(output)?;
+    let inputs = inputs
+        .split(',')
+        .map(parse_chemical)
+        .collect::<Result<_>>()?;
+    Ok((name, Reaction { quantity, inputs }))
+}
+
+impl Nanofactory {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut reactions = HashMap::new();
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, reaction) = parse_reaction(line)
+                .with_context(|| format!("Invalid reaction on line {}: {}", i + 1, line))?;
+            if reactions.insert(name.clone(), reaction).is_some() {
+                return Err(anyhow!(
+                    "Line {} is a second reaction producing {}",
+                    i + 1,
+                    name
+                ));
+            }
+        }
+
+        let mut order = Vec::new();
+        let mut visiting = Vec::new();
+        visit(&reactions, "FUEL", &mut visiting, &mut order)?;
+        order.reverse();
+        Ok(Self { reactions, order })
+    }
+
+    /// Ore needed for the given amount of fuel, or `None` if it doesn't fit
+    /// in a `u64`
+    pub fn ore_for_fuel(&self, fuel: u64) -> Option<u64> {
+        let mut needed: HashMap<&str, u64> = HashMap::new();
+        needed.insert("FUEL", fuel);
+        for name in &self.order {
+            let reaction = match self.reactions.get(name) {
+                Some(reaction) => reaction,
+                None => continue,
+            };
+            let amount = needed.get(name.as_str()).cloned().unwrap_or(0);
+            let batches = amount.div_ceil(reaction.quantity);
+            for (input, quantity) in &reaction.inputs {
+                let total = needed.entry(input.as_str()).or_default();
+                *total = total.checked_add(batches.checked_mul(*quantity)?)?;
+            }
+        }
+        Some(needed.get("ORE").cloned().unwrap_or(0))
+    }
+
+    /// Largest amount of fuel that can be made from the given ore
+    pub fn max_fuel(&self, ore: u64) -> u64 {
+        let enough = |fuel| self.ore_for_fuel(fuel).is_some_and(|needed| needed <= ore);
+        let (mut low, mut high) = (0, 1u64);
+        while enough(high) {
+            if high == u64::MAX {
+                return high;
+            }
+            low = high;
+            high = high.saturating_mul(2);
+        }
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if enough(mid) {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+}
+
+/// Depth first search that appends each chemical after everything it is made
+/// from
+fn visit<'a>(
+    reactions: &'a HashMap<String, Reaction>,
+    name: &'a str,
+    visiting: &mut Vec<&'a str>,
+    order: &mut Vec<String>,
+) -> Result<()> {
+    if order.iter().any(|done| done == name) {
+        return Ok(());
+    }
+    if visiting.contains(&name) {
+        return Err(anyhow!("Reactions for {} form a cycle", name));
+    }
+
+    match reactions.get(name) {
+        Some(reaction) => {
+            visiting.push(name);
+            for (input, _) in &reaction.inputs {
+                visit(reactions, input, visiting, order)?;
+            }
+            visiting.pop();
+        }
+        None if name == "ORE" => {}
+        None => return Err(anyhow!("No reaction produces {}", name)),
+    }
+    order.push(name.to_owned());
+    Ok(())
+}
+
+pub fn main(args: &[String]) -> Result<(u64, Option<u64>)> {
+    if args.len() != 1 {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    let factory = Nanofactory::parse(&std::fs::read_to_string(&args[0])?)?;
+    Ok((
+        factory
+            .ore_for_fuel(1)
+            .ok_or_else(|| anyhow!("Too much ore is needed for one fuel"))?,
+        Some(factory.max_fuel(1_000_000_000_000)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = "
+        10 ORE => 10 A
+        1 ORE => 1 B
+        7 A, 1 B => 1 C
+        7 A, 1 C => 1 D
+        7 A, 1 D => 1 E
+        7 A, 1 E => 1 FUEL
+    ";
+
+    const LARGE: &str = "
+        157 ORE => 5 NZVS
+        165 ORE => 6 DCFZ
+        44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL
+        12 HKGWZ, 1 GPVTF, 8 PSHF => 9 QDVJ
+        179 ORE => 7 PSHF
+        177 ORE => 5 HKGWZ
+        7 DCFZ, 7 PSHF => 2 XJWVT
+        165 ORE => 2 GPVTF
+        3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT
+    ";
+
+    #[test]
+    fn test_ore_for_fuel() -> Result<()> {
+        assert_eq!(Nanofactory::parse(SMALL)?.ore_for_fuel(1), Some(31));
+        assert_eq!(Nanofactory::parse(LARGE)?.ore_for_fuel(1), Some(13312));
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_fuel() -> Result<()> {
+        let factory = Nanofactory::parse(LARGE)?;
+        assert_eq!(factory.max_fuel(1_000_000_000_000), 82892753);
+        assert_eq!(factory.max_fuel(13311), 0);
+        assert_eq!(factory.max_fuel(13312), 1);
+
+        let factory = Nanofactory::parse("2 ORE => 1 FUEL")?;
+        assert_eq!(factory.ore_for_fuel(u64::MAX / 2), Some(u64::MAX - 1));
+        assert_eq!(factory.ore_for_fuel(u64::MAX / 2 + 1), None);
+        assert_eq!(factory.max_fuel(u64::MAX), u64::MAX / 2);
+
+        let factory = Nanofactory::parse("1 ORE => 1000000000 A\n1 A => 1000000000 FUEL")?;
+        assert_eq!(factory.ore_for_fuel(u64::MAX), Some(19));
+        assert_eq!(factory.max_fuel(19), u64::MAX);
+        assert_eq!(factory.max_fuel(18), 18_000_000_000_000_000_000);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Nanofactory::parse("10 ORE => 10 A\n7 A 1 B => 1 C").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid reaction on line 2: 7 A 1 B => 1 C"
+        );
+        assert!(Nanofactory::parse("10 ORE => 10 A\nx ORE => 1 FUEL").is_err());
+        assert!(Nanofactory::parse("10 ORE => 10 A\n1 A => 1 A\n1 A => 1 FUEL").is_err());
+        assert!(Nanofactory::parse("1 B => 1 FUEL").is_err());
+        assert!(Nanofactory::parse("1 FUEL => 1 B\n1 B => 1 FUEL").is_err());
+
+        let err = Nanofactory::parse("10 ORE => 10 A\n1 ORE => 0 FUEL").unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "Invalid reaction on line 2: 1 ORE => 0 FUEL: Quantity of FUEL must not be zero"
+        );
+        assert!(Nanofactory::parse("0 ORE => 1 FUEL").is_err());
+    }
+}
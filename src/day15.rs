@@ -0,0 +1,210 @@
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, VecDeque};
+
+use crate::coord::{Coord, Heading};
+use crate::grid::Canvas;
+use crate::intcode::{Interpreter, PausedInterpreterInput, State};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Cell {
+    Wall,
+    Open,
+    Oxygen,
+}
+
+/// Map explored by the droid, relative to where it started
+#[derive(Clone, Debug, PartialEq)]
+pub struct Survey {
+    pub map: Canvas<Cell>,
+    pub oxygen: Option<Coord>,
+    pub oxygen_distance: Option<usize>,
+}
+
+const MOVES: [(isize, Heading); 4] = [
+    (1, Heading::North),
+    (2, Heading::South),
+    (3, Heading::West),
+    (4, Heading::East),
+];
+
+/// Send a movement command and wait until the droid is ready for the next one
+fn try_move(
+    droid: PausedInterpreterInput,
+    command: isize,
+) -> Result<(Cell, PausedInterpreterInput)> {
+    let status = match droid.resume(command)? {
+        State::Output(c) => c,
+        _ => return Err(anyhow!("Droid did not report a status after moving")),
+    };
+    let cell = match status.get() {
+        0 => Cell::Wall,
+        1 => Cell::Open,
+        2 => Cell::Oxygen,
+        value => return Err(anyhow!("Invalid status {}", value)),
+    };
+    match status.resume()? {
+        State::Input(droid) => Ok((cell, droid)),
+        _ => Err(anyhow!("Droid stopped accepting commands")),
+    }
+}
+
+/// Breadth first search of the whole maze. Each branch continues from its own
+/// copy of the paused droid, so it never has to backtrack.
+pub fn explore(computer: Interpreter) -> Result<Survey> {
+    let droid = match computer.run()? {
+        State::Input(droid) => droid,
+        _ => return Err(anyhow!("Droid did not ask for a command")),
+    };
+
+    let mut survey = Survey {
+        map: Canvas::new(),
+        oxygen: None,
+        oxygen_distance: None,
+    };
+    survey.map.set(Coord::origin(), Cell::Open);
+
+    let mut queue = VecDeque::new();
+    queue.push_back((Coord::origin(), droid, 0));
+    while let Some((pos, droid, dist)) = queue.pop_front() {
+        for &(command, heading) in &MOVES {
+            let next = pos.offset(heading.direction(1));
+            if survey.map.get(next).is_some() {
+                continue;
+            }
+
+            let (cell, moved) = try_move(droid.clone(), command)?;
+            survey.map.set(next, cell);
+            if cell == Cell::Wall {
+                continue;
+            }
+            if cell == Cell::Oxygen && survey.oxygen.is_none() {
+                survey.oxygen = Some(next);
+                survey.oxygen_distance = Some(dist + 1);
+            }
+            queue.push_back((next, moved, dist + 1));
+        }
+    }
+    Ok(survey)
+}
+
+/// Number of steps from `start` to every reachable cell that isn't a wall
+pub fn distances(map: &Canvas<Cell>, start: Coord) -> HashMap<Coord, usize> {
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+    distances.insert(start, 0);
+    queue.push_back(start);
+    while let Some(pos) = queue.pop_front() {
+        let dist = distances[&pos];
+        for &(_, heading) in &MOVES {
+            let next = pos.offset(heading.direction(1));
+            match map.get(next) {
+                Some(Cell::Open) | Some(Cell::Oxygen) if !distances.contains_key(&next) => {
+                    distances.insert(next, dist + 1);
+                    queue.push_back(next);
+                }
+                _ => {}
+            }
+        }
+    }
+    distances
+}
+
+/// Minutes until oxygen spreading one cell per minute fills the map
+pub fn fill_time(map: &Canvas<Cell>, oxygen: Coord) -> usize {
+    distances(map, oxygen).values().cloned().max().unwrap_or(0)
+}
+
+pub fn main(args: &[String]) -> Result<(usize, Option<usize>)> {
+    if args.len() != 1 {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    let survey = explore(Interpreter::from_path(&args[0])?)?;
+    match (survey.oxygen, survey.oxygen_distance) {
+        (Some(oxygen), Some(distance)) => Ok((distance, Some(fill_time(&survey.map, oxygen)))),
+        _ => Err(anyhow!("Droid did not find the oxygen system")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    // Droid in the maze below, with the relative base pointing at its cell
+    //
+    //   #######
+    //   #...#.#
+    //   #.#.#.#
+    //   #.#O..#
+    //   #.###.#
+    //   #D....#
+    //   #######
+    const MAZE: &str = "
+                arb #map+36
+        loop:   in cmd
+                eq cmd, #1, t
+                mul t, #-7, d
+                eq cmd, #2, t
+                mul t, #7, t
+                add d, t, d
+                eq cmd, #3, t
+                mul t, #-1, t
+                add d, t, d
+                eq cmd, #4, t
+                add d, t, d
+                arb d
+                add @0, #0, c
+                jnz c, #moved
+                mul d, #-1, d
+                arb d
+        moved:  out c
+                jnz #1, #loop
+        cmd:    data 0
+        t:      data 0
+        d:      data 0
+        c:      data 0
+        map:    data 0, 0, 0, 0, 0, 0, 0
+                data 0, 1, 1, 1, 0, 1, 0
+                data 0, 1, 0, 1, 0, 1, 0
+                data 0, 1, 0, 2, 1, 1, 0
+                data 0, 1, 0, 0, 0, 1, 0
+                data 0, 1, 1, 1, 1, 1, 0
+                data 0, 0, 0, 0, 0, 0, 0
+    ";
+
+    #[test]
+    fn test_explore() -> Result<()> {
+        let survey = explore(assemble(MAZE)?)?;
+        assert_eq!(survey.oxygen, Some(Coord::new(2, 2)));
+        assert_eq!(survey.oxygen_distance, Some(8));
+        assert_eq!(survey.map.len(), 44);
+        assert_eq!(
+            survey
+                .map
+                .iter()
+                .filter(|&(_, &cell)| cell != Cell::Wall)
+                .count(),
+            18
+        );
+        assert_eq!(survey.map.get(Coord::new(-1, 0)), Some(&Cell::Wall));
+        assert_eq!(survey.map.get(Coord::new(4, 2)), Some(&Cell::Open));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fill_time() -> Result<()> {
+        let survey = explore(assemble(MAZE)?)?;
+        assert_eq!(fill_time(&survey.map, survey.oxygen.unwrap()), 8);
+        assert_eq!(distances(&survey.map, Coord::origin()).len(), 18);
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_oxygen() -> Result<()> {
+        let survey = explore(assemble(&MAZE.replace("0, 2, 1", "0, 1, 1"))?)?;
+        assert_eq!(survey.oxygen, None);
+        assert_eq!(survey.oxygen_distance, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::{anyhow, Result};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+
+pub fn parse_digits(line: &str) -> Result<Vec<i32>> {
+    line.trim()
+        .chars()
+        .map(|x| match x.to_digit(10) {
+            Some(digit) => Ok(digit as i32),
+            None => Err(anyhow!("Character is not a base 10 digit")),
+        })
+        .collect()
+}
+
+fn to_string(digits: &[i32]) -> String {
+    digits.iter().map(|digit| digit.to_string()).collect()
+}
+
+/// Apply one phase. Output digit `i` repeats each element of the pattern
+/// `0, 1, 0, -1` `i + 1` times, so it's a sum of runs which prefix sums give
+/// in constant time each.
+pub fn phase(signal: &[i32]) -> Vec<i32> {
+    let mut prefix = Vec::with_capacity(signal.len() + 1);
+    prefix.push(0);
+    for &digit in signal {
+        prefix.push(prefix[prefix.len() - 1] + digit);
+    }
+    let run = |start: usize, len: usize| {
+        let start = start.min(signal.len());
+        prefix[(start + len).min(signal.len())] - prefix[start]
+    };
+
+    (1..=signal.len())
+        .map(|k| {
+            let mut sum = 0;
+            let mut start = k - 1;
+            while start < signal.len() {
+                sum += run(start, k) - run(start + 2 * k, k);
+                start += 4 * k;
+            }
+            sum.abs() % 10
+        })
+        .collect()
+}
+
+pub fn fft(signal: &[i32], phases: usize) -> Vec<i32> {
+    let mut signal = signal.to_vec();
+    for _ in 0..phases {
+        signal = phase(&signal);
+    }
+    signal
+}
+
+/// Decode the message in the signal repeated 10000 times. The offset is
+/// always in the second half, where every pattern is zeros followed by ones,
+/// so each digit is the sum of the digits after it.
+pub fn message(signal: &[i32]) -> Result<String> {
+    if signal.len() < 7 {
+        return Err(anyhow!("Signal is too short to contain an offset"));
+    }
+    let offset = signal[..7].iter().fold(0, |acc, &d| acc * 10 + d as usize);
+    let len = signal.len() * 10_000;
+    if offset < len / 2 || offset + 8 > len {
+        return Err(anyhow!(
+            "Offset {} is not in the second half of the signal",
+            offset
+        ));
+    }
+
+    let mut tail: Vec<i32> = (offset..len).map(|i| signal[i % signal.len()]).collect();
+    for _ in 0..100 {
+        let mut sum = 0;
+        for digit in tail.iter_mut().rev() {
+            sum = (sum + *digit) % 10;
+            *digit = sum;
+        }
+    }
+    Ok(to_string(&tail[..8]))
+}
+
+pub fn main(args: &[String]) -> Result<(String, Option<String>)> {
+    if args.len() != 1 {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    let file = File::open(&args[0])?;
+    let reader = BufReader::new(file);
+    let line = reader
+        .lines()
+        .next()
+        .ok_or(anyhow!("Unable to read file"))??;
+
+    let signal = parse_digits(&line)?;
+    if signal.len() < 8 {
+        return Err(anyhow!("Signal must have at least 8 digits"));
+    }
+    Ok((to_string(&fft(&signal, 100)[..8]), Some(message(&signal)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_eight(signal: &str, phases: usize) -> Result<String> {
+        Ok(to_string(&fft(&parse_digits(signal)?, phases)[..8]))
+    }
+
+    #[test]
+    fn test_phase() -> Result<()> {
+        assert_eq!(first_eight("12345678", 1)?, "48226158");
+        assert_eq!(first_eight("12345678", 2)?, "34040438");
+        assert_eq!(first_eight("12345678", 4)?, "01029498");
+        assert!(parse_digits("12a4").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fft() -> Result<()> {
+        assert_eq!(
+            first_eight("80871224585914546619083218645595", 100)?,
+            "24176176"
+        );
+        assert_eq!(
+            first_eight("19617804207202209144916044189917", 100)?,
+            "73745418"
+        );
+        assert_eq!(
+            first_eight("69317163492948606335995924319873", 100)?,
+            "52432133"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_message() -> Result<()> {
+        assert_eq!(
+            message(&parse_digits("03036732577212944063491565474664")?)?,
+            "84462026"
+        );
+        assert_eq!(
+            message(&parse_digits("02935109699940807407585447034323")?)?,
+            "78725270"
+        );
+        assert_eq!(
+            message(&parse_digits("03081770884921959731165446850517")?)?,
+            "53553731"
+        );
+        assert!(message(&parse_digits("12345678")?).is_err());
+        Ok(())
+    }
+}
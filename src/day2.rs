@@ -1,12 +1,12 @@
 use anyhow::{anyhow, Result};
 
-use crate::intcode::{Interpreter, State};
+use crate::intcode::{Interpreter, Memory, State};
 
 fn adjust_and_compute(mut computer: Interpreter, noun: isize, verb: isize) -> Result<isize> {
     computer.put(1, noun);
     computer.put(2, verb);
     if let State::Halt(mem) = computer.run()? {
-        Ok(mem[&0])
+        Ok(mem.get(0))
     } else {
         Err(anyhow!(
             "Program tried to do IO, but it's not supported today"
@@ -40,6 +40,7 @@ pub fn main(args: &[String]) -> Result<(isize, Option<isize>)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::intcode::IntcodeError;
 
     #[test]
     fn test_adjust_and_compute() -> Result<()> {
@@ -49,4 +50,14 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_adjust_and_compute_error() {
+        let err =
+            adjust_and_compute(Interpreter::from_iter(vec![1, 0, 0, 0, 42]), 0, 0).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<IntcodeError>(),
+            Some(&IntcodeError::InvalidOpcode { pc: 4, opcode: 42 })
+        );
+    }
 }
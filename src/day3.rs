@@ -33,7 +33,7 @@ impl FromStr for Path {
 
     fn from_str(s: &str) -> Result<Path, Self::Err> {
         Ok(s.split(",")
-            .map(|dir| Ok(dir.parse::<Direction>()?))
+            .map(|dir| dir.parse::<Direction>())
             .collect::<Result<Vec<_>>>()?
             .into())
     }
@@ -11,7 +11,7 @@ where
             return false;
         }
     }
-    return true;
+    true
 }
 
 fn has_repetions<T, I>(it: T) -> bool
@@ -25,7 +25,7 @@ where
             return true;
         }
     }
-    return false;
+    false
 }
 
 fn has_pairs<T, I>(it: T) -> bool
@@ -54,7 +54,7 @@ where
 
         return true;
     }
-    return false;
+    false
 }
 
 fn is_valid_a(pw: &str) -> bool {
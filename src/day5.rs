@@ -1,28 +1,12 @@
 use anyhow::{anyhow, Result};
 
-use crate::intcode::{Interpreter, State};
+use crate::intcode::Interpreter;
 
 pub fn compute(computer: Interpreter, value: isize) -> Result<isize> {
-    let mut value = Some(value);
-    let mut output = None;
-    let mut state = computer.run()?;
-    loop {
-        match state {
-            State::Input(c) => {
-                state = c.resume(
-                    value
-                        .take()
-                        .ok_or(anyhow!("Input value already consumed"))?,
-                )?;
-            }
-            State::Output(c) => {
-                output = Some(c.get());
-                state = c.resume()?;
-            }
-            State::Halt(_) => break,
-        }
-    }
-    Ok(output.ok_or(anyhow!("No output produced by computer"))?)
+    computer
+        .run_with(vec![value])
+        .last()
+        .ok_or_else(|| anyhow!("No output produced by computer"))?
 }
 
 pub fn main(args: &[String]) -> Result<(isize, Option<isize>)> {
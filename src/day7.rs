@@ -0,0 +1,166 @@
+use anyhow::{anyhow, Result};
+
+use crate::intcode::network::{Control, Monitor, Network, Outcome, Packet};
+use crate::intcode::Interpreter;
+
+fn permutations(items: &[isize]) -> Vec<Vec<isize>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+
+    let mut output = Vec::new();
+    for i in 0..items.len() {
+        let mut rest = items.to_vec();
+        let first = rest.remove(i);
+        for mut permutation in permutations(&rest) {
+            permutation.insert(0, first);
+            output.push(permutation);
+        }
+    }
+    output
+}
+
+struct LastSignal {
+    from: usize,
+    signal: Option<isize>,
+}
+
+impl Monitor for LastSignal {
+    fn on_packet(&mut self, packet: &Packet) -> Control {
+        if packet.from == self.from {
+            self.signal = Some(packet.data[0]);
+        }
+        Control::Continue
+    }
+}
+
+fn run_amplifiers(computer: &Interpreter, phases: &[isize], feedback: bool) -> Result<isize> {
+    let num_amplifiers = phases.len();
+    let mut network = Network::new(
+        phases.iter().map(|_| computer.clone()),
+        move |from, data| Packet {
+            from,
+            to: if feedback {
+                (from + 1) % num_amplifiers
+            } else {
+                from + 1
+            },
+            data,
+        },
+    );
+    for (i, &phase) in phases.iter().enumerate() {
+        network.send(i, &[phase]);
+    }
+    network.send(0, &[0]);
+
+    let mut monitor = LastSignal {
+        from: num_amplifiers - 1,
+        signal: None,
+    };
+    match network.run(&mut monitor)? {
+        Outcome::Halted => monitor
+            .signal
+            .ok_or_else(|| anyhow!("Last amplifier produced no output")),
+        outcome => Err(anyhow!("Amplifiers did not halt ({:?})", outcome)),
+    }
+}
+
+fn run_chain(computer: &Interpreter, phases: &[isize]) -> Result<isize> {
+    run_amplifiers(computer, phases, false)
+}
+
+fn run_feedback_loop(computer: &Interpreter, phases: &[isize]) -> Result<isize> {
+    run_amplifiers(computer, phases, true)
+}
+
+fn max_signal<F>(computer: &Interpreter, phases: &[isize], run: F) -> Result<isize>
+where
+    F: Fn(&Interpreter, &[isize]) -> Result<isize>,
+{
+    permutations(phases)
+        .iter()
+        .map(|phases| run(computer, phases))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .max()
+        .ok_or_else(|| anyhow!("No phase settings given"))
+}
+
+pub fn main(args: &[String]) -> Result<(isize, Option<isize>)> {
+    if args.len() != 1 {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    let computer = Interpreter::from_path(&args[0])?;
+    Ok((
+        max_signal(&computer, &[0, 1, 2, 3, 4], run_chain)?,
+        Some(max_signal(&computer, &[5, 6, 7, 8, 9], run_feedback_loop)?),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permutations() {
+        assert_eq!(permutations(&[1]), vec![vec![1]]);
+        assert_eq!(
+            permutations(&[1, 2, 3]),
+            vec![
+                vec![1, 2, 3],
+                vec![1, 3, 2],
+                vec![2, 1, 3],
+                vec![2, 3, 1],
+                vec![3, 1, 2],
+                vec![3, 2, 1],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chain() -> Result<()> {
+        let computer = Interpreter::from_iter(vec![
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ]);
+        assert_eq!(run_chain(&computer, &[4, 3, 2, 1, 0])?, 43210);
+        assert_eq!(max_signal(&computer, &[0, 1, 2, 3, 4], run_chain)?, 43210);
+
+        let computer = Interpreter::from_iter(vec![
+            3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23, 101, 5, 23, 23, 1, 24, 23, 23, 4, 23,
+            99, 0, 0,
+        ]);
+        assert_eq!(max_signal(&computer, &[0, 1, 2, 3, 4], run_chain)?, 54321);
+
+        let computer = Interpreter::from_iter(vec![
+            3, 31, 3, 32, 1002, 32, 10, 32, 1001, 31, -2, 31, 1007, 31, 0, 33, 1002, 33, 7, 33, 1,
+            33, 31, 31, 1, 32, 31, 31, 4, 31, 99, 0, 0, 0,
+        ]);
+        assert_eq!(max_signal(&computer, &[0, 1, 2, 3, 4], run_chain)?, 65210);
+        Ok(())
+    }
+
+    #[test]
+    fn test_feedback_loop() -> Result<()> {
+        let computer = Interpreter::from_iter(vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ]);
+        assert_eq!(run_feedback_loop(&computer, &[9, 8, 7, 6, 5])?, 139629729);
+        assert_eq!(
+            max_signal(&computer, &[5, 6, 7, 8, 9], run_feedback_loop)?,
+            139629729
+        );
+
+        let computer = Interpreter::from_iter(vec![
+            3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001, 54,
+            -5, 54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53, 55, 53, 4,
+            53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
+        ]);
+        assert_eq!(
+            max_signal(&computer, &[5, 6, 7, 8, 9], run_feedback_loop)?,
+            18216
+        );
+        Ok(())
+    }
+}
@@ -7,7 +7,7 @@ use std::io::prelude::*;
 use std::io::BufReader;
 
 pub fn checksum(layer_size: usize, digits: &[u8]) -> Result<usize> {
-    if digits.len() % layer_size != 0 {
+    if !digits.len().is_multiple_of(layer_size) {
         return Err(anyhow!("Layer size must be evenly divisible by input"));
     }
     let fewest_zeroes = digits
@@ -27,7 +27,7 @@ pub fn checksum(layer_size: usize, digits: &[u8]) -> Result<usize> {
 
 pub fn render(width: usize, height: usize, digits: &[u8]) -> Result<String> {
     let layer_size = width * height;
-    if digits.len() % layer_size != 0 {
+    if !digits.len().is_multiple_of(layer_size) {
         return Err(anyhow!("Layer size must be evenly divisible by input"));
     }
 
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use crate::coord::Coord;
+
+/// Sparse grid of cells that can be rendered as text. By default the y axis
+/// points up like `Coord::offset` while a screen canvas has it pointing down.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Canvas<T> {
+    cells: HashMap<Coord, T>,
+    y_down: bool,
+}
+
+impl<T> Canvas<T> {
+    pub fn new() -> Self {
+        Self {
+            cells: HashMap::new(),
+            y_down: false,
+        }
+    }
+
+    pub fn screen() -> Self {
+        Self {
+            cells: HashMap::new(),
+            y_down: true,
+        }
+    }
+
+    pub fn get(&self, coord: Coord) -> Option<&T> {
+        self.cells.get(&coord)
+    }
+
+    pub fn set(&mut self, coord: Coord, value: T) -> Option<T> {
+        self.cells.insert(coord, value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Coord, &T)> {
+        self.cells.iter().map(|(&coord, value)| (coord, value))
+    }
+
+    /// Smallest and largest coordinate of the cells that have been set
+    pub fn bounds(&self) -> Option<(Coord, Coord)> {
+        let mut coords = self.cells.keys();
+        let first = *coords.next()?;
+        Some(coords.fold((first, first), |(min, max), c| {
+            (
+                Coord::new(min.x.min(c.x), min.y.min(c.y)),
+                Coord::new(max.x.max(c.x), max.y.max(c.y)),
+            )
+        }))
+    }
+
+    /// Draw every cell within the bounds, one line per row
+    pub fn render<F: Fn(Option<&T>) -> char>(&self, pixel: F) -> String {
+        let (min, max) = match self.bounds() {
+            Some(bounds) => bounds,
+            None => return String::new(),
+        };
+
+        let mut rows: Vec<isize> = (min.y..=max.y).collect();
+        if !self.y_down {
+            rows.reverse();
+        }
+        rows.into_iter()
+            .map(|y| {
+                (min.x..=max.x)
+                    .map(|x| pixel(self.get(Coord::new(x, y))))
+                    .collect()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl<T> Default for Canvas<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(cell: Option<&bool>) -> char {
+        match cell {
+            Some(true) => '#',
+            Some(false) => '.',
+            None => ' ',
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let mut canvas = Canvas::new();
+        assert_eq!(canvas.render(pixel), "");
+        assert_eq!(canvas.bounds(), None);
+
+        canvas.set(Coord::new(-1, 0), true);
+        canvas.set(Coord::new(1, 1), false);
+        assert_eq!(canvas.set(Coord::new(1, 1), true), Some(false));
+        assert_eq!(canvas.len(), 2);
+        assert_eq!(canvas.bounds(), Some((Coord::new(-1, 0), Coord::new(1, 1))));
+        assert_eq!(canvas.render(pixel), "  #\n#  ");
+
+        let mut screen = Canvas::screen();
+        for (coord, &value) in canvas.iter() {
+            screen.set(coord, value);
+        }
+        assert_eq!(screen.render(pixel), "#  \n  #");
+    }
+}
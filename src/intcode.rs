@@ -1,21 +1,103 @@
-use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use anyhow::Result;
 use std::convert::TryInto;
-use std::fs::read_to_string;
 use std::path::Path;
+use std::sync::Arc;
+
+pub mod analysis;
+pub mod ascii;
+pub mod asm;
+pub mod debugger;
+mod decoded;
+pub mod disasm;
+pub mod error;
+pub mod extension;
+pub mod history;
+pub mod io;
+pub mod limits;
+pub mod memory;
+pub mod network;
+pub mod profile;
+pub mod snapshot;
+pub mod trace;
+pub mod word;
+
+use decoded::DecodeCache;
+pub use error::IntcodeError;
+use extension::Extensions;
+use history::History;
+use limits::Budget;
+pub use limits::{Limit, Limits};
+pub use memory::{Memory, PagedMemory};
+use profile::Profile;
+use trace::{MemoryWrite, TraceRecord};
+pub use word::Word;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Mode {
     Pos,
     Immediate,
     Relative,
 }
 
+/// How `add` and `mul` deal with results that don't fit in a word
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arithmetic {
+    Checked,
+    Wrapping,
+}
+
+/// `Decoded` caches decoded instructions between executions. It gives the
+/// same results as `Standard` but runs instrumented machines the standard way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    Standard,
+    Decoded,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamKind {
+    Input,
+    Output,
+}
+
 #[derive(Debug, PartialEq, Eq)]
-pub enum State {
-    Input(PausedInterpreterInput),
-    Output(PausedInterpreterOutput),
-    Halt(HashMap<usize, isize>),
+pub struct InstructionInfo {
+    pub code: usize,
+    pub mnemonic: &'static str,
+    pub params: &'static [ParamKind],
+}
+
+pub const INSTRUCTIONS: [InstructionInfo; 10] = {
+    use ParamKind::*;
+    [
+        InstructionInfo::new(1, "add", &[Input, Input, Output]),
+        InstructionInfo::new(2, "mul", &[Input, Input, Output]),
+        InstructionInfo::new(3, "in", &[Output]),
+        InstructionInfo::new(4, "out", &[Input]),
+        InstructionInfo::new(5, "jnz", &[Input, Input]),
+        InstructionInfo::new(6, "jz", &[Input, Input]),
+        InstructionInfo::new(7, "lt", &[Input, Input, Output]),
+        InstructionInfo::new(8, "eq", &[Input, Input, Output]),
+        InstructionInfo::new(9, "arb", &[Input]),
+        InstructionInfo::new(99, "hlt", &[]),
+    ]
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step<W = isize> {
+    Continue,
+    Input(usize),
+    Output(W),
+    Limit(Limit),
+    Halt,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum State<M: Memory = PagedMemory> {
+    Input(PausedInterpreterInput<M>),
+    Output(PausedInterpreterOutput<M>),
+    Limit(PausedInterpreterLimit<M>),
+    Halt(M),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -24,22 +106,57 @@ pub struct Opcode {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Interpreter {
-    memory: HashMap<usize, isize>,
+pub struct Interpreter<M: Memory = PagedMemory> {
+    memory: M,
     pc: usize,
-    rel_base: isize,
+    op_pc: usize,
+    rel_base: M::Word,
+    arithmetic: Arithmetic,
+    engine: Engine,
+    decoded: DecodeCache<M::Word>,
+    extensions: Extensions<M>,
+    trace: Option<Vec<TraceRecord<M::Word>>>,
+    profile: Option<Box<Profile>>,
+    history: Option<History<M::Word>>,
+    budget: Option<Budget>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct PausedInterpreterInput {
-    inner: Interpreter,
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PausedInterpreterInput<M: Memory = PagedMemory> {
+    inner: Interpreter<M>,
     pos: usize,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PausedInterpreterOutput<M: Memory = PagedMemory> {
+    inner: Interpreter<M>,
+    value: M::Word,
+}
+
 #[derive(Debug, PartialEq, Eq)]
-pub struct PausedInterpreterOutput {
-    inner: Interpreter,
-    value: isize,
+pub struct PausedInterpreterLimit<M: Memory = PagedMemory> {
+    inner: Interpreter<M>,
+    limit: Limit,
+}
+
+impl InstructionInfo {
+    const fn new(code: usize, mnemonic: &'static str, params: &'static [ParamKind]) -> Self {
+        Self {
+            code,
+            mnemonic,
+            params,
+        }
+    }
+}
+
+impl Mode {
+    pub fn digit(&self) -> usize {
+        match self {
+            Mode::Pos => 0,
+            Mode::Immediate => 1,
+            Mode::Relative => 2,
+        }
+    }
 }
 
 impl Opcode {
@@ -51,197 +168,574 @@ impl Opcode {
         self.inner % 100
     }
 
-    pub fn param_mode(&self, i: u32) -> Result<Mode> {
-        match (self.inner / 10usize.pow(i + 2)) % 10 {
-            0 => Ok(Mode::Pos),
-            1 => Ok(Mode::Immediate),
-            2 => Ok(Mode::Relative),
-            mode => Err(anyhow!(
-                "Invalid parameter mode {} for parameter {}",
-                mode,
-                i
-            )),
+    pub fn mode_digit(&self, i: u32) -> usize {
+        (self.inner / 10usize.pow(i + 2)) % 10
+    }
+
+    pub fn param_mode(&self, i: u32) -> Option<Mode> {
+        match self.mode_digit(i) {
+            0 => Some(Mode::Pos),
+            1 => Some(Mode::Immediate),
+            2 => Some(Mode::Relative),
+            _ => None,
         }
     }
+
+    pub fn info(&self) -> Option<&'static InstructionInfo> {
+        INSTRUCTIONS.iter().find(|info| info.code == self.code())
+    }
 }
 
 impl Interpreter {
-    pub fn new(memory: HashMap<usize, isize>) -> Self {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(PagedMemory::from_path(path)?))
+    }
+
+    pub fn from_iter<T: IntoIterator<Item = isize>>(iter: T) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<M: Memory> Interpreter<M> {
+    pub fn new(memory: M) -> Self {
         Self {
             memory,
             pc: 0,
-            rel_base: 0,
+            op_pc: 0,
+            rel_base: M::Word::ZERO,
+            arithmetic: Arithmetic::Checked,
+            engine: Engine::Standard,
+            decoded: DecodeCache::new(),
+            extensions: Extensions::new(),
+            trace: None,
+            profile: None,
+            history: None,
+            budget: None,
         }
     }
 
-    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let str_input = read_to_string(path)?;
-        let program = str_input
-            .trim_end()
-            .split(",")
-            .enumerate()
-            .map(|(i, x)| -> Result<(usize, isize)> { Ok((i, x.parse()?)) })
-            .collect::<Result<HashMap<usize, isize>, _>>()?;
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn rel_base(&self) -> M::Word {
+        self.rel_base
+    }
+
+    pub fn arithmetic(&self) -> Arithmetic {
+        self.arithmetic
+    }
+
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.arithmetic = arithmetic;
+    }
+
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+
+    pub fn get(&self, i: usize) -> M::Word {
+        self.memory.get(i)
+    }
+
+    pub fn memory_size(&self) -> usize {
+        self.memory.size()
+    }
+
+    pub fn put(&mut self, pos: usize, value: M::Word) {
+        self.memory.put(pos, value);
+        self.decoded.invalidate(pos);
+    }
+
+    pub fn enable_tracing(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    pub fn disable_tracing(&mut self) -> Vec<TraceRecord<M::Word>> {
+        self.trace.take().unwrap_or_default()
+    }
+
+    pub fn take_trace(&mut self) -> Vec<TraceRecord<M::Word>> {
+        self.trace.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    fn trace_record(&mut self) -> Option<&mut TraceRecord<M::Word>> {
+        self.trace.as_mut().and_then(|trace| trace.last_mut())
+    }
+
+    pub fn enable_profiling(&mut self) {
+        self.profile.get_or_insert_with(Default::default);
+    }
+
+    pub fn disable_profiling(&mut self) -> Option<Profile> {
+        self.profile.take().map(|profile| *profile)
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_deref()
+    }
 
-        if program.len() == 0 {
-            return Err(anyhow!(
-                "Program is too short (expected at least 1 element, got 0)"
-            ));
+    /// Limits are kept in effect until cleared. Usage counted so far is kept
+    /// when replacing the limits of an interpreter that already has some.
+    pub fn set_limits(&mut self, limits: Limits) {
+        match self.budget.as_mut() {
+            Some(budget) => budget.limits = limits,
+            None => self.budget = Some(Budget::new(limits)),
         }
+    }
 
-        Ok(Self::new(program))
+    pub fn clear_limits(&mut self) {
+        self.budget = None;
     }
 
-    pub fn from_iter<T: IntoIterator<Item = isize>>(iter: T) -> Self {
-        Self::new(iter.into_iter().enumerate().collect())
+    pub fn instructions_executed(&self) -> Option<u64> {
+        self.budget.as_ref().map(|budget| budget.instructions)
     }
 
-    pub fn get(&self, i: usize) -> isize {
-        self.memory.get(&i).unwrap_or(&0).clone()
+    pub fn cells_touched(&self) -> Option<usize> {
+        self.budget.as_ref().map(|budget| budget.touched.len())
     }
 
-    pub fn put(&mut self, pos: usize, value: isize) {
-        *self.memory.entry(pos).or_insert(0) = value;
+    fn touch(&mut self, addr: usize) {
+        if let Some(budget) = self.budget.as_mut() {
+            budget.touch(addr);
+        }
     }
 
-    pub fn read_opcode(&mut self) -> Result<Opcode> {
-        Ok(Opcode::new(
-            self.read_input_param(Mode::Immediate)?.try_into()?,
-        ))
+    /// Register a handler for an opcode outside the base instruction set.
+    /// Handlers decode their own parameters and must advance past them.
+    pub fn register_opcode<F>(&mut self, code: usize, handler: F) -> Result<()>
+    where
+        F: Fn(&mut Self, Opcode) -> Result<Step<M::Word>, IntcodeError> + Send + Sync + 'static,
+    {
+        self.extensions.register(code, Arc::new(handler))
+    }
+
+    /// Write to memory the way instructions do, which unlike `put` is traced,
+    /// profiled, recorded in the history and counted against limits. Nothing
+    /// is written once the current instruction has gone over a limit, because
+    /// `step` is about to roll it back.
+    pub fn write(&mut self, pos: usize, value: M::Word) {
+        if self.budget.as_ref().is_some_and(Budget::is_exceeded) {
+            return;
+        }
+
+        let old = self.get(pos);
+        self.put(pos, value);
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record_write(pos);
+        }
+        if let Some(history) = self.history.as_mut() {
+            history.record_write(pos, old, value);
+        }
+        if let Some(record) = self.trace_record() {
+            record.write = Some(MemoryWrite {
+                addr: pos,
+                old,
+                new: value,
+            });
+        }
+    }
+
+    fn read(&mut self, addr: usize) -> M::Word {
+        self.touch(addr);
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record_read(addr);
+        }
+        self.get(addr)
+    }
+
+    fn param_index(&self) -> usize {
+        self.pc - self.op_pc - 1
     }
 
-    pub fn read_input_param(&mut self, mode: Mode) -> Result<isize> {
+    fn raw_opcode(&self) -> i128 {
+        self.get(self.op_pc).to_i128()
+    }
+
+    fn address(&self, address: M::Word, param: usize) -> Result<usize, IntcodeError> {
+        if address < M::Word::ZERO {
+            return Err(IntcodeError::NegativeAddress {
+                pc: self.op_pc,
+                opcode: self.raw_opcode(),
+                param,
+                address: address.to_i128(),
+            });
+        }
+        address
+            .to_isize()
+            .and_then(|address| address.try_into().ok())
+            .ok_or_else(|| IntcodeError::AddressOutOfRange {
+                pc: self.op_pc,
+                opcode: self.raw_opcode(),
+                param,
+                address: address.to_i128(),
+            })
+    }
+
+    fn overflow(&self, lhs: M::Word, rhs: M::Word) -> IntcodeError {
+        IntcodeError::Overflow {
+            pc: self.op_pc,
+            opcode: self.raw_opcode(),
+            lhs: lhs.to_i128(),
+            rhs: rhs.to_i128(),
+        }
+    }
+
+    fn sum(&self, lhs: M::Word, rhs: M::Word) -> Result<M::Word, IntcodeError> {
+        match self.arithmetic {
+            Arithmetic::Checked => lhs.checked_add(rhs).ok_or_else(|| self.overflow(lhs, rhs)),
+            Arithmetic::Wrapping => Ok(lhs.wrapping_add(rhs)),
+        }
+    }
+
+    fn product(&self, lhs: M::Word, rhs: M::Word) -> Result<M::Word, IntcodeError> {
+        match self.arithmetic {
+            Arithmetic::Checked => lhs.checked_mul(rhs).ok_or_else(|| self.overflow(lhs, rhs)),
+            Arithmetic::Wrapping => Ok(lhs.wrapping_mul(rhs)),
+        }
+    }
+
+    pub fn param_mode(&self, op: Opcode, i: u32) -> Result<Mode, IntcodeError> {
+        op.param_mode(i).ok_or_else(|| IntcodeError::InvalidMode {
+            pc: self.op_pc,
+            opcode: self.raw_opcode(),
+            param: i as usize,
+            mode: op.mode_digit(i),
+        })
+    }
+
+    pub fn read_opcode(&mut self) -> Result<Opcode, IntcodeError> {
+        self.op_pc = self.pc;
+        let opcode = self.get(self.pc);
+        self.pc += 1;
+        opcode
+            .to_isize()
+            .and_then(|opcode| opcode.try_into().ok())
+            .map(Opcode::new)
+            .ok_or_else(|| IntcodeError::InvalidOpcode {
+                pc: self.op_pc,
+                opcode: opcode.to_i128(),
+            })
+    }
+
+    pub fn read_input_param(&mut self, mode: Mode) -> Result<M::Word, IntcodeError> {
+        let param = self.param_index();
         let value = match mode {
-            Mode::Pos => self.get(self.get(self.pc).try_into()?),
+            Mode::Pos => {
+                let addr = self.address(self.get(self.pc), param)?;
+                self.read(addr)
+            }
             Mode::Immediate => self.get(self.pc),
-            Mode::Relative => self.get((self.rel_base + self.get(self.pc)).try_into()?),
+            Mode::Relative => {
+                let addr = self.address(self.sum(self.rel_base, self.get(self.pc))?, param)?;
+                self.read(addr)
+            }
         };
         self.pc += 1;
+        if let Some(record) = self.trace_record() {
+            record.modes.push(mode);
+            record.operands.push(value);
+        }
         Ok(value)
     }
 
-    pub fn read_output_param(&mut self, mode: Mode) -> Result<usize> {
+    pub fn read_output_param(&mut self, mode: Mode) -> Result<usize, IntcodeError> {
+        let param = self.param_index();
         let value = match mode {
             Mode::Pos => self.get(self.pc),
             Mode::Immediate => {
-                return Err(anyhow!("Output parameter must not be in immediate mode"))
+                return Err(IntcodeError::ImmediateOutput {
+                    pc: self.op_pc,
+                    opcode: self.raw_opcode(),
+                    param,
+                })
             }
-            Mode::Relative => self.rel_base + self.get(self.pc),
+            Mode::Relative => self.sum(self.rel_base, self.get(self.pc))?,
         };
         self.pc += 1;
-        Ok(value.try_into()?)
+        if let Some(record) = self.trace_record() {
+            record.modes.push(mode);
+            record.operands.push(value);
+        }
+        let addr = self.address(value, param)?;
+        self.touch(addr);
+        Ok(addr)
     }
 
-    fn read_binop_params(&mut self, op: Opcode) -> Result<(isize, isize, usize)> {
+    fn read_binop_params(&mut self, op: Opcode) -> Result<(M::Word, M::Word, usize), IntcodeError> {
         Ok((
-            self.read_input_param(op.param_mode(0)?)?,
-            self.read_input_param(op.param_mode(1)?)?,
-            self.read_output_param(op.param_mode(2)?)?,
+            self.read_input_param(self.param_mode(op, 0)?)?,
+            self.read_input_param(self.param_mode(op, 1)?)?,
+            self.read_output_param(self.param_mode(op, 2)?)?,
         ))
     }
 
-    pub fn add(&mut self, op: Opcode) -> Result<()> {
+    pub fn add(&mut self, op: Opcode) -> Result<(), IntcodeError> {
         let (a, b, output) = self.read_binop_params(op)?;
-        self.put(output, a + b);
+        let value = self.sum(a, b)?;
+        self.write(output, value);
         Ok(())
     }
 
-    pub fn multiply(&mut self, op: Opcode) -> Result<()> {
+    pub fn multiply(&mut self, op: Opcode) -> Result<(), IntcodeError> {
         let (a, b, output) = self.read_binop_params(op)?;
-        self.put(output, a * b);
+        let value = self.product(a, b)?;
+        self.write(output, value);
         Ok(())
     }
 
-    pub fn jump(&mut self, op: Opcode) -> Result<()> {
-        let cmp = self.read_input_param(op.param_mode(0)?)?;
-        let jmp_target = self.read_input_param(op.param_mode(1)?)?;
-        if (cmp != 0) == (op.code() == 5) {
-            self.pc = jmp_target.try_into()?;
+    pub fn jump(&mut self, op: Opcode) -> Result<(), IntcodeError> {
+        let cmp = self.read_input_param(self.param_mode(op, 0)?)?;
+        let jmp_target = self.read_input_param(self.param_mode(op, 1)?)?;
+        let taken = (cmp != M::Word::ZERO) == (op.code() == 5);
+        if taken {
+            let target = self.address(jmp_target, 1)?;
+            if let Some(budget) = self.budget.as_mut() {
+                budget.check_address(target);
+            }
+            self.pc = target;
+        }
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record_branch(taken);
         }
 
         Ok(())
     }
 
-    pub fn less_than(&mut self, op: Opcode) -> Result<()> {
+    pub fn less_than(&mut self, op: Opcode) -> Result<(), IntcodeError> {
         let (a, b, output) = self.read_binop_params(op)?;
         if a < b {
-            self.put(output, 1);
+            self.write(output, M::Word::ONE);
         } else {
-            self.put(output, 0);
+            self.write(output, M::Word::ZERO);
         }
         Ok(())
     }
 
-    pub fn equal(&mut self, op: Opcode) -> Result<()> {
+    pub fn equal(&mut self, op: Opcode) -> Result<(), IntcodeError> {
         let (a, b, output) = self.read_binop_params(op)?;
         if a == b {
-            self.put(output, 1);
+            self.write(output, M::Word::ONE);
         } else {
-            self.put(output, 0);
+            self.write(output, M::Word::ZERO);
         }
         Ok(())
     }
 
-    pub fn set_rel_base(&mut self, op: Opcode) -> Result<()> {
-        self.rel_base += self.read_input_param(op.param_mode(0)?)?;
+    pub fn set_rel_base(&mut self, op: Opcode) -> Result<(), IntcodeError> {
+        let offset = self.read_input_param(self.param_mode(op, 0)?)?;
+        self.rel_base = self.sum(self.rel_base, offset)?;
         Ok(())
     }
 
-    pub fn run(mut self) -> Result<State> {
+    /// Execute a single instruction. An instruction that goes over a limit is
+    /// rolled back so it can be retried: its memory writes are dropped and the
+    /// pc, relative base, trace and history are restored. Memory reads
+    /// already counted by the profiler, cells added to the touched set before
+    /// the limit was hit and any side effects of extension handlers are kept.
+    pub fn step(&mut self) -> Result<Step<M::Word>, IntcodeError> {
+        if let Some(limit) = self.budget.as_mut().and_then(Budget::before_step) {
+            return Ok(Step::Limit(limit));
+        }
+
+        let pc = self.pc;
+        let rel_base = self.rel_base;
+        let op = self.read_opcode()?;
+        let raw = self.get(pc);
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(TraceRecord::new(pc, raw));
+        }
+        if let Some(history) = self.history.as_mut() {
+            history.push(pc, raw, rel_base);
+        }
+
+        let step = self.execute(op)?;
+
+        // Roll back instructions that went over a limit so they can be retried
+        if let Some(limit) = self.budget.as_mut().and_then(Budget::after_step) {
+            self.pc = pc;
+            self.rel_base = rel_base;
+            if let Some(trace) = self.trace.as_mut() {
+                trace.pop();
+            }
+            if let Some(history) = self.history.as_mut() {
+                history.pop();
+            }
+            return Ok(Step::Limit(limit));
+        }
+
+        let new_rel_base = self.rel_base;
+        if new_rel_base != rel_base {
+            if let Some(record) = self.trace_record() {
+                record.rel_base = Some((rel_base, new_rel_base));
+            }
+        }
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record_step(pc, op.code(), self.pc, new_rel_base.cmp(&rel_base));
+        }
+        Ok(step)
+    }
+
+    fn execute(&mut self, op: Opcode) -> Result<Step<M::Word>, IntcodeError> {
+        match op.code() {
+            1 => self.add(op)?,
+            2 => self.multiply(op)?,
+            3 => {
+                let mode = self.param_mode(op, 0)?;
+                return Ok(Step::Input(self.read_output_param(mode)?));
+            }
+            4 => {
+                let mode = self.param_mode(op, 0)?;
+                return Ok(Step::Output(self.read_input_param(mode)?));
+            }
+            5 | 6 => self.jump(op)?,
+            7 => self.less_than(op)?,
+            8 => self.equal(op)?,
+            9 => self.set_rel_base(op)?,
+            99 => return Ok(Step::Halt),
+            code => {
+                if let Some(handler) = self.extensions.get(code).cloned() {
+                    return handler(self, op);
+                }
+                return Err(IntcodeError::InvalidOpcode {
+                    pc: self.op_pc,
+                    opcode: self.raw_opcode(),
+                });
+            }
+        }
+        Ok(Step::Continue)
+    }
+
+    fn is_instrumented(&self) -> bool {
+        self.trace.is_some()
+            || self.profile.is_some()
+            || self.history.is_some()
+            || self.budget.is_some()
+    }
+
+    pub fn run(mut self) -> Result<State<M>, IntcodeError> {
+        let decoded = self.engine == Engine::Decoded && !self.is_instrumented();
         loop {
-            let op = self.read_opcode()?;
-            match op.code() {
-                1 => self.add(op)?,
-                2 => self.multiply(op)?,
-                3 => {
-                    let pos = self.read_output_param(op.param_mode(0)?)?;
-                    return Ok(State::Input(PausedInterpreterInput { inner: self, pos }));
+            let step = if decoded {
+                self.step_decoded()?
+            } else {
+                self.step()?
+            };
+            match step {
+                Step::Continue => {}
+                Step::Input(pos) => {
+                    return Ok(State::Input(PausedInterpreterInput { inner: self, pos }))
                 }
-                4 => {
-                    let value = self.read_input_param(op.param_mode(0)?)?;
+                Step::Output(value) => {
                     return Ok(State::Output(PausedInterpreterOutput {
                         inner: self,
                         value,
-                    }));
+                    }))
+                }
+                Step::Limit(limit) => {
+                    return Ok(State::Limit(PausedInterpreterLimit { inner: self, limit }))
                 }
-                5 | 6 => self.jump(op)?,
-                7 => self.less_than(op)?,
-                8 => self.equal(op)?,
-                9 => self.set_rel_base(op)?,
-                99 => return Ok(State::Halt(self.memory)),
-                op => return Err(anyhow!("Got invalid opcode {}", op)),
+                Step::Halt => return Ok(State::Halt(self.memory)),
             }
         }
     }
 }
 
-impl PausedInterpreterInput {
-    pub fn resume(mut self, value: isize) -> Result<State> {
-        self.inner.put(self.pos, value);
+impl<M: Memory> PausedInterpreterInput<M> {
+    pub fn resume(mut self, value: M::Word) -> Result<State<M>, IntcodeError> {
+        self.inner.write(self.pos, value);
         self.inner.run()
     }
 }
 
-impl PausedInterpreterOutput {
-    pub fn get(&self) -> isize {
+impl<M: Memory> PausedInterpreterOutput<M> {
+    pub fn get(&self) -> M::Word {
         self.value
     }
 
-    pub fn resume(self) -> Result<State> {
+    pub fn resume(self) -> Result<State<M>, IntcodeError> {
         self.inner.run()
     }
+
+    pub fn into_interpreter(self) -> Interpreter<M> {
+        self.inner
+    }
+}
+
+impl<M: Memory> PausedInterpreterLimit<M> {
+    pub fn limit(&self) -> Limit {
+        self.limit
+    }
+
+    pub fn interpreter(&self) -> &Interpreter<M> {
+        &self.inner
+    }
+
+    pub fn resume(self) -> Result<State<M>, IntcodeError> {
+        self.inner.run()
+    }
+
+    pub fn resume_with(mut self, limits: Limits) -> Result<State<M>, IntcodeError> {
+        self.inner.set_limits(limits);
+        self.inner.run()
+    }
+
+    pub fn into_interpreter(self) -> Interpreter<M> {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+impl<M: Memory + std::fmt::Debug> State<M> {
+    pub(crate) fn expect_input(self) -> PausedInterpreterInput<M> {
+        match self {
+            State::Input(c) => c,
+            state => panic!("Expected input, got {:?}", state),
+        }
+    }
+
+    pub(crate) fn expect_output(self) -> PausedInterpreterOutput<M> {
+        match self {
+            State::Output(c) => c,
+            state => panic!("Expected output, got {:?}", state),
+        }
+    }
+
+    pub(crate) fn expect_limit(self) -> PausedInterpreterLimit<M> {
+        match self {
+            State::Limit(c) => c,
+            state => panic!("Expected limit, got {:?}", state),
+        }
+    }
+
+    pub(crate) fn expect_halt(self) -> M {
+        match self {
+            State::Halt(memory) => memory,
+            state => panic!("Expected halt, got {:?}", state),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
 
     fn run(mem: Vec<isize>) -> Result<State> {
-        Interpreter::from_iter(mem).run()
+        Ok(Interpreter::from_iter(mem).run()?)
     }
 
     fn halt(mem: Vec<isize>) -> State {
-        State::Halt(mem.into_iter().enumerate().collect())
+        State::Halt(mem.into_iter().collect())
     }
 
     #[test]
@@ -263,4 +757,176 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_hash_map_memory() -> Result<()> {
+        let memory: HashMap<usize, isize> = vec![1, 0, 0, 0, 99].into_iter().enumerate().collect();
+        assert_eq!(Interpreter::new(memory).run()?.expect_halt()[&0], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_instruction_limit() -> Result<()> {
+        let mut computer = Interpreter::from_iter(vec![1105, 1, 0]);
+        computer.set_limits(Limits::new().max_instructions(100));
+
+        let c = computer.run()?.expect_limit();
+        assert_eq!(c.limit(), Limit::Instructions(100));
+        assert_eq!(c.interpreter().instructions_executed(), Some(100));
+
+        let c = c
+            .resume_with(Limits::new().max_instructions(150))?
+            .expect_limit();
+        assert_eq!(c.interpreter().instructions_executed(), Some(150));
+        Ok(())
+    }
+
+    #[test]
+    fn test_address_limit() -> Result<()> {
+        let mut computer = Interpreter::from_iter(vec![1101, 1, 1, 5000, 99]);
+        computer.set_limits(Limits::new().max_address(1000));
+
+        let c = computer.run()?.expect_limit();
+        assert_eq!(c.limit(), Limit::Address(5000));
+        assert_eq!(c.interpreter().pc(), 0);
+        assert_eq!(c.interpreter().get(5000), 0);
+
+        assert_eq!(c.resume_with(Limits::new())?.expect_halt().get(5000), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_jump_address_limit() -> Result<()> {
+        let mut computer = Interpreter::from_iter(vec![1105, 1, 5000]);
+        computer.set_limits(Limits::new().max_address(1000));
+
+        let c = computer.run()?.expect_limit();
+        assert_eq!(c.limit(), Limit::Address(5000));
+        assert_eq!(c.interpreter().pc(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cell_limit() -> Result<()> {
+        // Writes to a new address on every iteration
+        let mut computer = Interpreter::from_iter(vec![1101, 0, 0, 100, 1001, 3, 1, 3, 1105, 1, 0]);
+        computer.set_limits(Limits::new().max_cells(3));
+
+        let c = computer.run()?.expect_limit();
+        assert_eq!(c.limit(), Limit::Cells(3));
+        assert_eq!(c.interpreter().cells_touched(), Some(3));
+        assert_eq!(c.interpreter().pc(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deadline() -> Result<()> {
+        let mut computer = Interpreter::from_iter(vec![1105, 1, 0]);
+        computer.set_limits(Limits::new().timeout(std::time::Duration::from_millis(10)));
+        assert_eq!(computer.run()?.expect_limit().limit(), Limit::Deadline);
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow() -> Result<()> {
+        let program = vec![1102, isize::MAX, 2, 5, 99, 0];
+        assert_eq!(
+            Interpreter::from_iter(program.clone()).run().unwrap_err(),
+            IntcodeError::Overflow {
+                pc: 0,
+                opcode: 1102,
+                lhs: isize::MAX as i128,
+                rhs: 2,
+            }
+        );
+
+        let mut computer = Interpreter::from_iter(program.clone());
+        computer.set_arithmetic(Arithmetic::Wrapping);
+        assert_eq!(computer.run()?.expect_halt().get(5), -2);
+
+        let memory: PagedMemory<i128> = program.into_iter().map(|x| x as i128).collect();
+        assert_eq!(
+            Interpreter::new(memory).run()?.expect_halt().get(5),
+            isize::MAX as i128 * 2
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_wide_words() -> Result<()> {
+        let computer = Interpreter::new(PagedMemory::<i128>::from_path("data/day9.txt")?);
+        assert_eq!(
+            computer.run_with(vec![1]).collect::<Result<Vec<_>>>()?,
+            vec![3100786347]
+        );
+
+        let computer = Interpreter::new(PagedMemory::<i64>::from_path("data/day9.txt")?);
+        assert_eq!(
+            computer.run_with(vec![1]).collect::<Result<Vec<_>>>()?,
+            vec![3100786347]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_address_out_of_range() {
+        let memory: PagedMemory<i128> = vec![1, 0, 0, 1 << 80, 99].into_iter().collect();
+        assert_eq!(
+            Interpreter::new(memory).run().unwrap_err(),
+            IntcodeError::AddressOutOfRange {
+                pc: 0,
+                opcode: 1,
+                param: 2,
+                address: 1 << 80,
+            }
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |mem: Vec<isize>| Interpreter::from_iter(mem).run().unwrap_err();
+        assert_eq!(
+            err(vec![1101, 1, 1, 5, 42]),
+            IntcodeError::InvalidOpcode { pc: 4, opcode: 42 }
+        );
+        assert_eq!(
+            err(vec![-1]),
+            IntcodeError::InvalidOpcode { pc: 0, opcode: -1 }
+        );
+        assert_eq!(
+            err(vec![301, 0, 0, 0]),
+            IntcodeError::InvalidMode {
+                pc: 0,
+                opcode: 301,
+                param: 0,
+                mode: 3
+            }
+        );
+        assert_eq!(
+            err(vec![10001, 0, 0, 0]),
+            IntcodeError::ImmediateOutput {
+                pc: 0,
+                opcode: 10001,
+                param: 2
+            }
+        );
+        assert_eq!(
+            err(vec![1, 0, -3, 0]),
+            IntcodeError::NegativeAddress {
+                pc: 0,
+                opcode: 1,
+                param: 1,
+                address: -3
+            }
+        );
+        assert_eq!(
+            err(vec![1105, 1, -7]),
+            IntcodeError::NegativeAddress {
+                pc: 0,
+                opcode: 1105,
+                param: 1,
+                address: -7
+            }
+        );
+    }
 }
@@ -0,0 +1,644 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryInto;
+use std::fmt;
+
+use super::disasm::decode;
+use super::{Interpreter, Memory, Mode, Opcode, ParamKind};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Issue {
+    InvalidOpcode {
+        addr: usize,
+        opcode: isize,
+    },
+    InvalidMode {
+        addr: usize,
+        opcode: isize,
+        param: usize,
+        mode: usize,
+    },
+    ImmediateOutput {
+        addr: usize,
+        opcode: isize,
+        param: usize,
+    },
+    SelfModifying {
+        addr: usize,
+        target: usize,
+    },
+    UnboundedLoop {
+        addr: usize,
+        io: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Exit {
+    Next,
+    Halt,
+    /// Jump whose target is only known at runtime
+    Indirect,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub addr: usize,
+    pub len: usize,
+    pub text: String,
+    pub successors: Vec<usize>,
+    pub exit: Exit,
+    writes: Vec<usize>,
+    io: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub start: usize,
+    pub instructions: Vec<usize>,
+    pub successors: Vec<usize>,
+    pub exit: Exit,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Loop {
+    pub blocks: Vec<usize>,
+    pub exits: bool,
+    pub io: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis {
+    pub size: usize,
+    pub instructions: BTreeMap<usize, Instruction>,
+    pub blocks: BTreeMap<usize, Block>,
+    pub loops: Vec<Loop>,
+    pub issues: Vec<Issue>,
+}
+
+fn explore<M: Memory<Word = isize>>(
+    computer: &Interpreter<M>,
+    addr: usize,
+    issues: &mut Vec<Issue>,
+) -> Instruction {
+    let raw = computer.get(addr);
+    let mut instruction = Instruction {
+        addr,
+        len: 1,
+        text: format!("data {}", raw),
+        successors: Vec::new(),
+        exit: Exit::Error,
+        writes: Vec::new(),
+        io: false,
+    };
+
+    let opcode = raw.try_into().ok().map(Opcode::new);
+    let info = match opcode.and_then(|op| op.info()) {
+        Some(info) => info,
+        None => {
+            issues.push(Issue::InvalidOpcode { addr, opcode: raw });
+            return instruction;
+        }
+    };
+    let opcode = opcode.unwrap();
+    instruction.len = info.params.len() + 1;
+
+    let mut operands = Vec::new();
+    for (i, kind) in info.params.iter().enumerate() {
+        let value = computer.get(addr + i + 1);
+        match (opcode.param_mode(i as u32), kind) {
+            (None, _) => {
+                issues.push(Issue::InvalidMode {
+                    addr,
+                    opcode: raw,
+                    param: i,
+                    mode: opcode.mode_digit(i as u32),
+                });
+                return instruction;
+            }
+            (Some(Mode::Immediate), ParamKind::Output) => {
+                issues.push(Issue::ImmediateOutput {
+                    addr,
+                    opcode: raw,
+                    param: i,
+                });
+                return instruction;
+            }
+            (Some(Mode::Pos), ParamKind::Output) => {
+                if let Ok(target) = value.try_into() {
+                    instruction.writes.push(target);
+                }
+            }
+            _ => {}
+        }
+        operands.push((opcode.param_mode(i as u32).unwrap(), value));
+    }
+
+    instruction.text = decode(computer, addr)
+        .map(|item| item.to_string())
+        .unwrap_or_else(|| format!("{} {:?}", info.mnemonic, operands));
+    instruction.io = matches!(opcode.code(), 3 | 4);
+    instruction.exit = Exit::Next;
+
+    let next = addr + instruction.len;
+    match opcode.code() {
+        99 => instruction.exit = Exit::Halt,
+        code @ 5 | code @ 6 => {
+            let taken = match operands[0] {
+                (Mode::Immediate, cond) => Some((cond != 0) == (code == 5)),
+                _ => None,
+            };
+            if taken != Some(false) {
+                match operands[1] {
+                    (Mode::Immediate, target) => match target.try_into() {
+                        Ok(target) => instruction.successors.push(target),
+                        Err(_) => instruction.exit = Exit::Error,
+                    },
+                    _ => instruction.exit = Exit::Indirect,
+                }
+            }
+            if taken != Some(true) {
+                instruction.successors.push(next);
+            }
+        }
+        _ => instruction.successors.push(next),
+    }
+    instruction
+}
+
+struct Tarjan<'a> {
+    blocks: &'a BTreeMap<usize, Block>,
+    index: BTreeMap<usize, usize>,
+    low: BTreeMap<usize, usize>,
+    stack: Vec<usize>,
+    on_stack: BTreeSet<usize>,
+    components: Vec<Vec<usize>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn enter(&mut self, block: usize) {
+        let index = self.index.len();
+        self.index.insert(block, index);
+        self.low.insert(block, index);
+        self.stack.push(block);
+        self.on_stack.insert(block);
+    }
+
+    /// Iterative so that long chains of blocks can't overflow the call stack.
+    /// Successors that aren't the start of a block are skipped.
+    fn visit(&mut self, root: usize) {
+        self.enter(root);
+        let mut work = vec![(root, 0)];
+        while let Some((block, i)) = work.last_mut() {
+            let block = *block;
+            let successors = self.blocks.get(&block).map_or(&[][..], |b| &b.successors);
+            if let Some(&next) = successors.get(*i) {
+                *i += 1;
+                if !self.blocks.contains_key(&next) {
+                    continue;
+                }
+                if !self.index.contains_key(&next) {
+                    self.enter(next);
+                    work.push((next, 0));
+                } else if self.on_stack.contains(&next) {
+                    let low = self.low[&block].min(self.index[&next]);
+                    self.low.insert(block, low);
+                }
+                continue;
+            }
+
+            work.pop();
+            if let Some(&(parent, _)) = work.last() {
+                let low = self.low[&parent].min(self.low[&block]);
+                self.low.insert(parent, low);
+            }
+            if self.low[&block] == self.index[&block] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack.remove(&member);
+                    component.push(member);
+                    if member == block {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                self.components.push(component);
+            }
+        }
+    }
+}
+
+fn find_loops(
+    blocks: &BTreeMap<usize, Block>,
+    instructions: &BTreeMap<usize, Instruction>,
+) -> Vec<Loop> {
+    let mut tarjan = Tarjan {
+        blocks,
+        index: BTreeMap::new(),
+        low: BTreeMap::new(),
+        stack: Vec::new(),
+        on_stack: BTreeSet::new(),
+        components: Vec::new(),
+    };
+    for &start in blocks.keys() {
+        if !tarjan.index.contains_key(&start) {
+            tarjan.visit(start);
+        }
+    }
+
+    let mut loops: Vec<_> = tarjan
+        .components
+        .into_iter()
+        .filter(|component| {
+            component.len() > 1 || blocks[&component[0]].successors.contains(&component[0])
+        })
+        .map(|component| {
+            let members: BTreeSet<_> = component.iter().cloned().collect();
+            let exits = component.iter().any(|start| {
+                let block = &blocks[start];
+                block.exit != Exit::Next
+                    || block.successors.iter().any(|next| !members.contains(next))
+            });
+            let io = component
+                .iter()
+                .flat_map(|start| &blocks[start].instructions)
+                .any(|addr| instructions[addr].io);
+            Loop {
+                blocks: component,
+                exits,
+                io,
+            }
+        })
+        .collect();
+    loops.sort_by_key(|l| l.blocks[0]);
+    loops
+}
+
+/// Follow control flow from address 0 to separate code from data and find
+/// instructions that are guaranteed or likely to misbehave
+pub fn analyze<M: Memory<Word = isize>>(computer: &Interpreter<M>) -> Analysis {
+    let mut issues = Vec::new();
+    let mut instructions = BTreeMap::new();
+    let mut pending = vec![0];
+    while let Some(addr) = pending.pop() {
+        if instructions.contains_key(&addr) {
+            continue;
+        }
+        let instruction = explore(computer, addr, &mut issues);
+        pending.extend(instruction.successors.iter().cloned());
+        instructions.insert(addr, instruction);
+    }
+
+    let code: BTreeSet<usize> = instructions
+        .values()
+        .flat_map(|i| i.addr..i.addr + i.len)
+        .collect();
+    for instruction in instructions.values() {
+        for &target in &instruction.writes {
+            if code.contains(&target) {
+                issues.push(Issue::SelfModifying {
+                    addr: instruction.addr,
+                    target,
+                });
+            }
+        }
+    }
+
+    // Blocks start at the entry point, at jump targets, after branches and
+    // wherever an instruction doesn't run straight into the next one, such as
+    // when instructions overlap
+    let straight = |instruction: &Instruction, following: Option<&usize>| {
+        let next = instruction.addr + instruction.len;
+        instruction.exit == Exit::Next
+            && instruction.successors == [next]
+            && following == Some(&next)
+    };
+    let mut leaders: BTreeSet<usize> = vec![0].into_iter().collect();
+    let mut following = instructions.keys().skip(1);
+    for instruction in instructions.values() {
+        if !straight(instruction, following.next()) {
+            leaders.extend(instruction.successors.iter().cloned());
+            leaders.insert(instruction.addr + instruction.len);
+        }
+    }
+
+    let new_block = |start| Block {
+        start,
+        instructions: Vec::new(),
+        successors: Vec::new(),
+        exit: Exit::Next,
+    };
+    let mut blocks = BTreeMap::new();
+    let mut current: Option<Block> = None;
+    let mut following = instructions.keys().skip(1);
+    for instruction in instructions.values() {
+        let mut block = match current.take() {
+            Some(block) if !leaders.contains(&instruction.addr) => block,
+            Some(block) => {
+                blocks.insert(block.start, block);
+                new_block(instruction.addr)
+            }
+            None => new_block(instruction.addr),
+        };
+        block.instructions.push(instruction.addr);
+        block.successors = instruction.successors.clone();
+        block.exit = instruction.exit;
+
+        if straight(instruction, following.next()) {
+            current = Some(block);
+        } else {
+            blocks.insert(block.start, block);
+        }
+    }
+    blocks.extend(current.map(|block| (block.start, block)));
+
+    let loops = find_loops(&blocks, &instructions);
+    for l in &loops {
+        if !l.exits {
+            issues.push(Issue::UnboundedLoop {
+                addr: l.blocks[0],
+                io: l.io,
+            });
+        }
+    }
+    issues.sort_by_key(|issue| issue.addr());
+
+    Analysis {
+        size: computer.memory_size(),
+        instructions,
+        blocks,
+        loops,
+        issues,
+    }
+}
+
+impl Analysis {
+    pub fn code_cells(&self) -> usize {
+        self.instructions
+            .values()
+            .flat_map(|i| i.addr..i.addr + i.len)
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+
+    pub fn summary(&self) -> String {
+        let code = self.code_cells();
+        let mut output = format!(
+            "Reachable instructions: {} ({} cells)\nData cells: {}\nBasic blocks: {}\nLoops: {}\n",
+            self.instructions.len(),
+            code,
+            self.size.saturating_sub(code),
+            self.blocks.len(),
+            self.loops.len(),
+        );
+        if self.issues.is_empty() {
+            output.push_str("No issues found\n");
+        } else {
+            output.push_str("Issues:\n");
+            for issue in &self.issues {
+                output.push_str(&format!("  {}\n", issue));
+            }
+        }
+        output
+    }
+
+    /// Control flow graph in Graphviz DOT format with one node per basic block
+    pub fn to_dot(&self) -> String {
+        let mut output =
+            String::from("digraph cfg {\n    node [shape=box, fontname=\"monospace\"];\n");
+        let mut indirect = false;
+        for block in self.blocks.values() {
+            let label: String = block
+                .instructions
+                .iter()
+                .map(|addr| format!("{:04}: {}\\l", addr, self.instructions[addr].text))
+                .collect();
+            let style = match block.exit {
+                Exit::Halt => ", peripheries=2",
+                Exit::Error => ", color=red",
+                Exit::Next | Exit::Indirect => "",
+            };
+            output.push_str(&format!(
+                "    b{} [label=\"{}\"{}];\n",
+                block.start, label, style
+            ));
+            for next in &block.successors {
+                output.push_str(&format!("    b{} -> b{};\n", block.start, next));
+            }
+            if block.exit == Exit::Indirect {
+                output.push_str(&format!(
+                    "    b{} -> indirect [style=dashed];\n",
+                    block.start
+                ));
+                indirect = true;
+            }
+        }
+        if indirect {
+            output.push_str("    indirect [shape=ellipse, label=\"?\"];\n");
+        }
+        output.push_str("}\n");
+        output
+    }
+}
+
+impl Issue {
+    pub fn addr(&self) -> usize {
+        match *self {
+            Issue::InvalidOpcode { addr, .. }
+            | Issue::InvalidMode { addr, .. }
+            | Issue::ImmediateOutput { addr, .. }
+            | Issue::SelfModifying { addr, .. }
+            | Issue::UnboundedLoop { addr, .. } => addr,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Issue::InvalidOpcode { addr, opcode } => {
+                write!(f, "{:04}: invalid opcode {}", addr, opcode)
+            }
+            Issue::InvalidMode {
+                addr,
+                opcode,
+                param,
+                mode,
+            } => write!(
+                f,
+                "{:04}: invalid mode {} for parameter {} of opcode {}",
+                addr, mode, param, opcode
+            ),
+            Issue::ImmediateOutput {
+                addr,
+                opcode,
+                param,
+            } => write!(
+                f,
+                "{:04}: output parameter {} of opcode {} is in immediate mode",
+                addr, param, opcode
+            ),
+            Issue::SelfModifying { addr, target } => {
+                write!(f, "{:04}: writes to code at {:04}", addr, target)
+            }
+            Issue::UnboundedLoop { addr, io } => write!(
+                f,
+                "{:04}: loop has no exit{}",
+                addr,
+                if io { " (performs I/O)" } else { "" }
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+    use anyhow::Result;
+
+    #[test]
+    fn test_code_and_data() -> Result<()> {
+        let analysis = analyze(&assemble(
+            "
+                  in x
+            loop: add x, #-1, x
+                  jnz x, #loop
+                  hlt
+            x:    data 0
+            ",
+        )?);
+        assert_eq!(
+            analysis.instructions.keys().cloned().collect::<Vec<_>>(),
+            vec![0, 2, 6, 9]
+        );
+        assert_eq!(analysis.code_cells(), 10);
+        assert_eq!(
+            analysis.blocks.keys().cloned().collect::<Vec<_>>(),
+            vec![0, 2, 9]
+        );
+        assert_eq!(analysis.blocks[&2].successors, vec![2, 9]);
+        assert_eq!(
+            analysis.loops,
+            vec![Loop {
+                blocks: vec![2],
+                exits: true,
+                io: false
+            }]
+        );
+        assert!(analysis.issues.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_issues() {
+        let issues = |program: Vec<isize>| analyze(&Interpreter::from_iter(program)).issues;
+        assert_eq!(
+            issues(vec![1, 0, 0, 0, 99]),
+            vec![Issue::SelfModifying { addr: 0, target: 0 }]
+        );
+        assert_eq!(
+            issues(vec![11101, 1, 1, 5, 99]),
+            vec![Issue::ImmediateOutput {
+                addr: 0,
+                opcode: 11101,
+                param: 2
+            }]
+        );
+        assert_eq!(
+            issues(vec![301, 0, 0, 0]),
+            vec![Issue::InvalidMode {
+                addr: 0,
+                opcode: 301,
+                param: 0,
+                mode: 3
+            }]
+        );
+        assert_eq!(
+            issues(vec![1105, 1, 3, 42]),
+            vec![Issue::InvalidOpcode {
+                addr: 3,
+                opcode: 42
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unbounded_loops() -> Result<()> {
+        let analysis = analyze(&assemble("loop: jnz #1, #loop")?);
+        assert_eq!(
+            analysis.issues,
+            vec![Issue::UnboundedLoop { addr: 0, io: false }]
+        );
+
+        let analysis = analyze(&assemble("loop: in x\n out x\n jz #0, #loop\n x: data 0")?);
+        assert_eq!(
+            analysis.issues,
+            vec![Issue::UnboundedLoop { addr: 0, io: true }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlapping_instructions() {
+        // The jump at 2 lands in the middle of the output instruction at 0
+        let analysis = analyze(&Interpreter::from_iter(vec![
+            104, 1106, 1105, 1, 1, 0, 0, 9, 99, 0,
+        ]));
+        assert_eq!(
+            analysis.blocks.keys().cloned().collect::<Vec<_>>(),
+            vec![0, 1, 2, 4]
+        );
+        assert_eq!(analysis.blocks[&0].successors, vec![2]);
+        assert_eq!(analysis.blocks[&4].instructions, vec![4, 8]);
+        for block in analysis.blocks.values() {
+            for next in &block.successors {
+                assert!(analysis.blocks.contains_key(next));
+            }
+        }
+        assert!(analysis.loops.is_empty());
+    }
+
+    #[test]
+    fn test_long_chain() {
+        // Enough blocks in a row to overflow the stack with a recursive search.
+        // Each one may either fall through or jump to the final halt.
+        let halt = 19_999 * 3;
+        let mut program: Vec<isize> = (0..19_999).flat_map(|_| vec![1006, 0, halt]).collect();
+        program.push(99);
+        let analysis = analyze(&Interpreter::from_iter(program));
+        assert_eq!(analysis.blocks.len(), 20_000);
+        assert!(analysis.loops.is_empty());
+    }
+
+    #[test]
+    fn test_dot() -> Result<()> {
+        let dot = analyze(&assemble("jz x, @0\nhlt\nx: data 1")?).to_dot();
+        assert_eq!(
+            dot,
+            [
+                "digraph cfg {",
+                "    node [shape=box, fontname=\"monospace\"];",
+                "    b0 [label=\"0000: jz 4, @0\\l\"];",
+                "    b0 -> b3;",
+                "    b0 -> indirect [style=dashed];",
+                "    b3 [label=\"0003: hlt\\l\", peripheries=2];",
+                "    indirect [shape=ellipse, label=\"?\"];",
+                "}",
+                "",
+            ]
+            .join("\n")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_puzzle_inputs() -> Result<()> {
+        for path in &["data/day2.txt", "data/day5.txt", "data/day9.txt"] {
+            let analysis = analyze(&Interpreter::from_path(path)?);
+            assert!(!analysis.blocks.is_empty());
+            assert!(analysis.summary().starts_with("Reachable instructions"));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+
+use super::io::next_input;
+use super::{Interpreter, Memory, PagedMemory, State};
+
+/// A run of printable output or a single value outside the ASCII range
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Output {
+    Text(String),
+    Value(isize),
+}
+
+/// Adapter for programs that read and write lines of ASCII text
+pub struct AsciiConsole<M: Memory<Word = isize> = PagedMemory> {
+    state: Option<State<M>>,
+    input: VecDeque<isize>,
+}
+
+fn push_output(output: &mut Vec<Output>, value: isize) {
+    match value {
+        0..=127 => {
+            let c = value as u8 as char;
+            match output.last_mut() {
+                Some(Output::Text(text)) => text.push(c),
+                _ => output.push(Output::Text(c.to_string())),
+            }
+        }
+        _ => output.push(Output::Value(value)),
+    }
+}
+
+impl<M: Memory<Word = isize>> AsciiConsole<M> {
+    pub fn new(computer: Interpreter<M>) -> Result<Self> {
+        Ok(Self {
+            state: Some(computer.run()?),
+            input: VecDeque::new(),
+        })
+    }
+
+    /// Queue a line of input, a newline is appended
+    pub fn send_line(&mut self, line: &str) -> Result<()> {
+        if let Some(c) = line.chars().find(|c| !c.is_ascii()) {
+            return Err(anyhow!("Can't send non-ASCII character {:?}", c));
+        }
+        self.input.extend(line.bytes().map(isize::from));
+        self.input.push_back(10);
+        Ok(())
+    }
+
+    /// Send a raw value, bypassing the text encoding
+    pub fn send_value(&mut self, value: isize) {
+        self.input.push_back(value);
+    }
+
+    pub fn is_halted(&self) -> bool {
+        matches!(self.state, Some(State::Halt(_)) | None)
+    }
+
+    /// Run until the program halts or wants input that hasn't been queued
+    pub fn run(&mut self) -> Result<Vec<Output>> {
+        let mut output = Vec::new();
+        let mut state = self
+            .state
+            .take()
+            .ok_or_else(|| anyhow!("Program failed earlier"))?;
+        loop {
+            state = match state {
+                State::Input(c) => match self.input.pop_front() {
+                    Some(value) => c.resume(value)?,
+                    None => {
+                        self.state = Some(State::Input(c));
+                        return Ok(output);
+                    }
+                },
+                State::Output(c) => {
+                    push_output(&mut output, c.get());
+                    c.resume()?
+                }
+                State::Limit(c) => return Err(anyhow!("{}", c.limit())),
+                State::Halt(memory) => {
+                    self.state = Some(State::Halt(memory));
+                    return Ok(output);
+                }
+            }
+        }
+    }
+
+    /// Run and return the output as text, failing on any non-ASCII value
+    pub fn run_text(&mut self) -> Result<String> {
+        self.run()?
+            .into_iter()
+            .map(|output| match output {
+                Output::Text(text) => Ok(text),
+                Output::Value(value) => Err(anyhow!("Program output non-ASCII value {}", value)),
+            })
+            .collect()
+    }
+}
+
+/// Connect a program to a text stream, sending each line read from `input`
+/// whenever the program runs out of input. Values outside the ASCII range are
+/// written on a line of their own.
+pub fn interactive<M, R, W>(console: &mut AsciiConsole<M>, input: R, mut output: W) -> Result<()>
+where
+    M: Memory<Word = isize>,
+    R: BufRead,
+    W: Write,
+{
+    let mut lines = input.lines();
+    loop {
+        for chunk in console.run()? {
+            match chunk {
+                Output::Text(text) => write!(output, "{}", text)?,
+                Output::Value(value) => writeln!(output, "{}", value)?,
+            }
+        }
+        output.flush()?;
+
+        if console.is_halted() {
+            return Ok(());
+        }
+        console.send_line(&next_input(&mut lines)??)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    // Echoes lines until it reads an empty one and then prints the length of
+    // the longest line times 1000 as a raw value
+    const ECHO: &str = "
+        start: add #0, #0, len
+        loop:  in c
+               out c
+               eq c, #10, t
+               jnz t, #line
+               add len, #1, len
+               jnz #1, #loop
+        line:  jz len, #end
+               lt max, len, t
+               jz t, #start
+               add len, #0, max
+               jnz #1, #start
+        end:   out #62
+               out #32
+               mul max, #1000, max
+               out max
+               hlt
+        c:     data 0
+        t:     data 0
+        len:   data 0
+        max:   data 0
+    ";
+
+    #[test]
+    fn test_console() -> Result<()> {
+        let mut console = AsciiConsole::new(assemble(ECHO)?)?;
+        assert_eq!(console.run()?, vec![]);
+        assert!(!console.is_halted());
+
+        console.send_line("hello")?;
+        console.send_line("hi")?;
+        assert_eq!(console.run_text()?, "hello\nhi\n");
+
+        console.send_line("")?;
+        assert_eq!(
+            console.run()?,
+            vec![Output::Text("\n> ".to_owned()), Output::Value(5000)]
+        );
+        assert!(console.is_halted());
+        assert_eq!(console.run()?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_ascii() -> Result<()> {
+        let mut console = AsciiConsole::new(assemble(ECHO)?)?;
+        assert!(console.send_line("héllo").is_err());
+
+        console.send_value(200);
+        console.send_value(10);
+        console.send_value(10);
+        assert!(console.run_text().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_interactive() -> Result<()> {
+        let mut console = AsciiConsole::new(assemble(ECHO)?)?;
+        let mut output = Vec::new();
+        interactive(&mut console, "abc\n\n".as_bytes(), &mut output)?;
+        assert_eq!(String::from_utf8(output)?, "abc\n\n> 3000\n");
+
+        let mut console = AsciiConsole::new(assemble(ECHO)?)?;
+        assert!(interactive(&mut console, "abc\n".as_bytes(), Vec::new()).is_err());
+        Ok(())
+    }
+}
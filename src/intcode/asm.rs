@@ -0,0 +1,296 @@
+use anyhow::{anyhow, Error, Result};
+use std::collections::HashMap;
+
+use super::{Interpreter, Mode, ParamKind, INSTRUCTIONS};
+
+#[derive(Clone, Copy, Debug)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    col: usize,
+}
+
+#[derive(Debug)]
+enum Statement<'a> {
+    Instruction {
+        code: usize,
+        operands: Vec<(Mode, Token<'a>)>,
+    },
+    Data(Vec<Token<'a>>),
+}
+
+impl<'a> Token<'a> {
+    fn new(line_no: usize, line: &'a str, text: &'a str) -> Self {
+        let offset = text.as_ptr() as usize - line.as_ptr() as usize;
+        Self {
+            text,
+            line: line_no,
+            col: line[..offset].chars().count() + 1,
+        }
+    }
+
+    fn error<T: std::fmt::Display>(&self, msg: T) -> Error {
+        anyhow!("{}:{}: {}", self.line, self.col, msg)
+    }
+
+    fn is_identifier(&self) -> bool {
+        let mut chars = self.text.chars();
+        match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            }
+            _ => false,
+        }
+    }
+
+    fn eval(&self, labels: &HashMap<&str, usize>) -> Result<isize> {
+        // A leading sign belongs to the number itself
+        let split = self
+            .text
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| c == '+' || c == '-')
+            .map_or(self.text.len(), |(i, _)| i);
+        let (base, offset) = self.text.split_at(split);
+        let base = base.trim_end();
+
+        let base_value = if let Ok(value) = base.parse::<isize>() {
+            value
+        } else {
+            let label = Token {
+                text: base,
+                ..*self
+            };
+            if !label.is_identifier() {
+                return Err(self.error(format!("Invalid expression {:?}", self.text)));
+            }
+            *labels
+                .get(base)
+                .ok_or_else(|| self.error(format!("Undefined label {:?}", base)))?
+                as isize
+        };
+
+        if offset.is_empty() {
+            return Ok(base_value);
+        }
+
+        let offset_value = offset[1..]
+            .trim()
+            .parse::<isize>()
+            .map_err(|_| self.error(format!("Invalid offset in {:?}", self.text)))?;
+        if offset.starts_with('-') {
+            base_value.checked_sub(offset_value)
+        } else {
+            base_value.checked_add(offset_value)
+        }
+        .ok_or_else(|| self.error(format!("Operand out of range {:?}", self.text)))
+    }
+}
+
+fn split_operands<'a>(line_no: usize, line: &'a str, rest: &'a str) -> Result<Vec<Token<'a>>> {
+    if rest.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    rest.split(',')
+        .map(|operand| {
+            let trimmed = operand.trim();
+            if trimmed.is_empty() {
+                Err(Token::new(line_no, line, operand).error("Expected operand"))
+            } else {
+                Ok(Token::new(line_no, line, trimmed))
+            }
+        })
+        .collect()
+}
+
+fn parse_statement<'a>(line_no: usize, line: &'a str, code: &'a str) -> Result<Statement<'a>> {
+    let split = code.find(char::is_whitespace).unwrap_or(code.len());
+    let mnemonic = Token::new(line_no, line, &code[..split]);
+    let operands = split_operands(line_no, line, &code[split..])?;
+
+    if mnemonic.text == "data" {
+        if operands.is_empty() {
+            return Err(mnemonic.error("Expected at least one value for data"));
+        }
+        for operand in operands.iter() {
+            if operand.text.starts_with(['#', '@']) {
+                return Err(operand.error("Addressing modes are not allowed in data"));
+            }
+        }
+        return Ok(Statement::Data(operands));
+    }
+
+    let info = INSTRUCTIONS
+        .iter()
+        .find(|info| info.mnemonic == mnemonic.text)
+        .ok_or_else(|| mnemonic.error(format!("Unknown mnemonic {:?}", mnemonic.text)))?;
+
+    if operands.len() != info.params.len() {
+        return Err(mnemonic.error(format!(
+            "{} expects {} operands, got {}",
+            info.mnemonic,
+            info.params.len(),
+            operands.len()
+        )));
+    }
+
+    let operands = operands
+        .into_iter()
+        .zip(info.params.iter())
+        .map(|(operand, kind)| {
+            let (mode, text) = if let Some(text) = operand.text.strip_prefix('#') {
+                (Mode::Immediate, text)
+            } else if let Some(text) = operand.text.strip_prefix('@') {
+                (Mode::Relative, text)
+            } else {
+                (Mode::Pos, operand.text)
+            };
+
+            if mode == Mode::Immediate && *kind == ParamKind::Output {
+                return Err(operand.error("Output parameter must not be in immediate mode"));
+            }
+            if text.trim().is_empty() {
+                return Err(operand.error("Expected value after addressing mode"));
+            }
+            Ok((mode, Token::new(line_no, line, text.trim())))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Statement::Instruction {
+        code: info.code,
+        operands,
+    })
+}
+
+pub fn compile(source: &str) -> Result<Vec<isize>> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+    let mut addr = 0;
+
+    for (i, line) in source.lines().enumerate() {
+        let line_no = i + 1;
+        let mut code = line.split(';').next().unwrap_or("").trim();
+
+        while let Some(end) = code.find(':') {
+            let label = Token::new(line_no, line, code[..end].trim());
+            if !label.is_identifier() {
+                return Err(label.error(format!("Invalid label {:?}", label.text)));
+            }
+            if labels.insert(label.text, addr).is_some() {
+                return Err(label.error(format!("Duplicate label {:?}", label.text)));
+            }
+            code = code[end + 1..].trim_start();
+        }
+
+        if code.is_empty() {
+            continue;
+        }
+
+        let statement = parse_statement(line_no, line, code)?;
+        addr += match &statement {
+            Statement::Instruction { operands, .. } => operands.len() + 1,
+            Statement::Data(values) => values.len(),
+        };
+        statements.push(statement);
+    }
+
+    let mut words = Vec::with_capacity(addr);
+    for statement in statements {
+        match statement {
+            Statement::Instruction { code, operands } => {
+                let modes: usize = operands
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (mode, _))| mode.digit() * 10usize.pow(i as u32 + 2))
+                    .sum();
+                words.push((code + modes) as isize);
+                for (_, operand) in operands {
+                    words.push(operand.eval(&labels)?);
+                }
+            }
+            Statement::Data(values) => {
+                for value in values {
+                    words.push(value.eval(&labels)?);
+                }
+            }
+        }
+    }
+
+    Ok(words)
+}
+
+pub fn assemble(source: &str) -> Result<Interpreter> {
+    Ok(Interpreter::from_iter(compile(source)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile() -> Result<()> {
+        assert_eq!(compile("mul 4, #3, 4\nhlt")?, vec![1002, 4, 3, 4, 99]);
+        assert_eq!(compile("add @-1, #5, @7")?, vec![21201, -1, 5, 7]);
+        assert_eq!(
+            compile("in 0\nout #-3\narb #2")?,
+            vec![3, 0, 104, -3, 109, 2]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_labels_and_data() -> Result<()> {
+        let source = "
+            ; Count down from 3 and output each value
+            loop: out n
+                  add n, #-1, n
+                  jnz n, #loop
+            end:  hlt
+            n:    data 3
+            tail: data end, n+1, n-4
+        ";
+        assert_eq!(
+            compile(source)?,
+            vec![4, 10, 1001, 10, -1, 10, 1005, 10, 0, 99, 3, 9, 11, 6],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_assemble() -> Result<()> {
+        let computer = assemble("in x\nmul x, #2, x\nout x\nhlt\nx: data 0")?;
+        let c = computer.run()?.expect_input().resume(21)?.expect_output();
+        assert_eq!(c.get(), 42);
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |source| compile(source).unwrap_err().to_string();
+        assert_eq!(err("hlt\n  foo 1"), "2:3: Unknown mnemonic \"foo\"");
+        assert_eq!(err("add 1, 2"), "1:1: add expects 3 operands, got 2");
+        assert_eq!(
+            err("add 1, 2, #3"),
+            "1:11: Output parameter must not be in immediate mode",
+        );
+        assert_eq!(err("jz #0, #nope"), "1:9: Undefined label \"nope\"");
+        assert_eq!(err("a: hlt\n a: hlt"), "2:2: Duplicate label \"a\"");
+        assert_eq!(err("data 1, , 2"), "1:8: Expected operand");
+        assert_eq!(
+            err("data #1"),
+            "1:6: Addressing modes are not allowed in data"
+        );
+        assert_eq!(err("out 1x"), "1:5: Invalid expression \"1x\"");
+        assert_eq!(err("out é"), "1:5: Invalid expression \"é\"");
+        assert_eq!(err("out é+1"), "1:5: Invalid expression \"é+1\"");
+        assert_eq!(
+            err("out 9223372036854775807+1"),
+            "1:5: Operand out of range \"9223372036854775807+1\""
+        );
+        assert_eq!(
+            err("out -9223372036854775807-2"),
+            "1:5: Operand out of range \"-9223372036854775807-2\""
+        );
+    }
+}
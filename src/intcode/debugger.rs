@@ -0,0 +1,490 @@
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+use std::io::{BufRead, Write};
+
+use super::disasm::{decode, Item};
+use super::{Interpreter, Limit, Step};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Stepped,
+    Breakpoint(usize),
+    Watchpoint { addr: usize, old: isize, new: isize },
+    Input(usize),
+    Output(isize),
+    Limit(Limit),
+    Halt,
+}
+
+#[derive(Clone, Debug)]
+pub struct Debugger {
+    computer: Interpreter,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeSet<usize>,
+    input: VecDeque<isize>,
+    pending_input: Option<usize>,
+    halted: bool,
+    /// Breakpoint that the last `cont` stopped on, which the next one skips
+    stopped_at: Option<usize>,
+}
+
+impl Debugger {
+    pub fn new(computer: Interpreter) -> Self {
+        Self {
+            computer,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+            input: VecDeque::new(),
+            pending_input: None,
+            halted: false,
+            stopped_at: None,
+        }
+    }
+
+    /// Record every executed instruction so it can be undone. The history
+    /// grows with each step, so it is off unless asked for.
+    pub fn with_history(mut self) -> Self {
+        self.computer.enable_history();
+        self
+    }
+
+    pub fn has_history(&self) -> bool {
+        self.computer.history().is_some()
+    }
+
+    pub fn interpreter(&self) -> &Interpreter {
+        &self.computer
+    }
+
+    pub fn into_interpreter(self) -> Interpreter {
+        self.computer
+    }
+
+    pub fn pc(&self) -> usize {
+        self.computer.pc()
+    }
+
+    pub fn rel_base(&self) -> isize {
+        self.computer.rel_base()
+    }
+
+    /// Up to `len` cells starting at `start`, stopping at the end of memory
+    pub fn memory(&self, start: usize, len: usize) -> Vec<isize> {
+        let size = self.computer.memory_size();
+        let end = start.checked_add(len).map_or(size, |end| end.min(size));
+        (start..end).map(|i| self.computer.get(i)).collect()
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn add_breakpoint(&mut self, addr: usize) -> bool {
+        self.breakpoints.insert(addr)
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: usize) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.breakpoints.iter().cloned()
+    }
+
+    pub fn add_watchpoint(&mut self, addr: usize) -> bool {
+        self.watchpoints.insert(addr)
+    }
+
+    pub fn remove_watchpoint(&mut self, addr: usize) -> bool {
+        self.watchpoints.remove(&addr)
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.watchpoints.iter().cloned()
+    }
+
+    pub fn feed(&mut self, value: isize) {
+        self.input.push_back(value);
+    }
+
+    /// Undo up to `n` instructions. Input consumed by undone instructions is
+    /// queued again.
+    pub fn step_back(&mut self, n: usize) -> usize {
+        let mut undone = 0;
+        while undone < n {
+            let entry = match self.computer.step_back() {
+                Some(entry) => entry,
+                None => break,
+            };
+            if entry.opcode % 100 == 3 {
+                if let Some(write) = entry.writes.last() {
+                    self.input.push_front(write.new);
+                }
+            }
+            undone += 1;
+        }
+        if undone > 0 {
+            self.halted = false;
+            self.pending_input = None;
+            self.stopped_at = None;
+        }
+        undone
+    }
+
+    /// Rewind to just before the instruction that last wrote to `addr` and
+    /// return its address
+    pub fn rewind_to_write(&mut self, addr: usize) -> Option<usize> {
+        let history = self.computer.history()?;
+        let (step, entry, _) = history.last_write(addr)?;
+        let pc = entry.pc;
+        self.step_back(history.len() - step);
+        Some(pc)
+    }
+
+    pub fn last_writer(&self, addr: usize) -> Option<usize> {
+        self.computer.last_writer(addr)
+    }
+
+    pub fn current(&self) -> Item {
+        decode(&self.computer, self.pc())
+            .unwrap_or_else(|| Item::Data(self.computer.get(self.pc())))
+    }
+
+    fn write_input(&mut self, pos: usize) -> Option<Event> {
+        let value = self.input.pop_front()?;
+        let old = self.computer.get(pos);
+        self.computer.write(pos, value);
+        self.pending_input = None;
+
+        Some(if self.watchpoints.contains(&pos) && old != value {
+            Event::Watchpoint {
+                addr: pos,
+                old,
+                new: value,
+            }
+        } else {
+            Event::Stepped
+        })
+    }
+
+    pub fn step(&mut self) -> Result<Event> {
+        self.stopped_at = None;
+        if self.halted {
+            return Ok(Event::Halt);
+        }
+
+        if let Some(pos) = self.pending_input {
+            return Ok(self.write_input(pos).unwrap_or(Event::Input(pos)));
+        }
+
+        let watched: Vec<_> = self
+            .watchpoints
+            .iter()
+            .map(|&addr| (addr, self.computer.get(addr)))
+            .collect();
+
+        match self.computer.step()? {
+            Step::Continue => {}
+            Step::Input(pos) => {
+                self.pending_input = Some(pos);
+                return Ok(self.write_input(pos).unwrap_or(Event::Input(pos)));
+            }
+            Step::Output(value) => return Ok(Event::Output(value)),
+            Step::Limit(limit) => return Ok(Event::Limit(limit)),
+            Step::Halt => {
+                self.halted = true;
+                return Ok(Event::Halt);
+            }
+        }
+
+        for (addr, old) in watched {
+            let new = self.computer.get(addr);
+            if old != new {
+                return Ok(Event::Watchpoint { addr, old, new });
+            }
+        }
+        Ok(Event::Stepped)
+    }
+
+    /// Run until something stops execution. A breakpoint on the current
+    /// instruction stops it straight away unless it is the one already
+    /// stopped on.
+    pub fn cont(&mut self) -> Result<Event> {
+        let pc = self.pc();
+        if self.breakpoints.contains(&pc) && self.stopped_at != Some(pc) && !self.halted {
+            self.stopped_at = Some(pc);
+            return Ok(Event::Breakpoint(pc));
+        }
+
+        loop {
+            match self.step()? {
+                Event::Stepped => {}
+                event => return Ok(event),
+            }
+
+            let pc = self.pc();
+            if self.breakpoints.contains(&pc) {
+                self.stopped_at = Some(pc);
+                return Ok(Event::Breakpoint(pc));
+            }
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Stepped => write!(f, "Stepped"),
+            Event::Breakpoint(addr) => write!(f, "Breakpoint at {}", addr),
+            Event::Watchpoint { addr, old, new } => {
+                write!(f, "Watchpoint at {}: {} -> {}", addr, old, new)
+            }
+            Event::Input(addr) => write!(f, "Waiting for input to {}", addr),
+            Event::Output(value) => write!(f, "Output {}", value),
+            Event::Limit(limit) => write!(f, "{}", limit),
+            Event::Halt => write!(f, "Halted"),
+        }
+    }
+}
+
+const HELP: &str = "\
+step [n]         Execute n instructions (default 1)
+continue         Run until a breakpoint, watchpoint, input, output or halt
+back [n]         Undo n instructions (default 1), needs history
+rewind <addr>    Undo up to and including the last write to addr, needs history
+who <addr>       Show which instruction last wrote to addr, needs history
+break <addr>     Set a breakpoint
+watch <addr>     Stop when the value at the given address changes
+delete <addr>    Remove breakpoint and watchpoint on the given address
+input <v>...     Queue values for input instructions
+regs             Show pc and relative base
+mem <addr> [n]   Show n memory cells starting at addr (default 1)
+quit             Exit the debugger
+";
+
+fn parse_arg<T: std::str::FromStr>(arg: Option<&str>) -> Result<T> {
+    arg.ok_or_else(|| anyhow!("Missing argument"))?
+        .parse()
+        .map_err(|_| anyhow!("Invalid argument"))
+}
+
+fn execute<W: Write>(debugger: &mut Debugger, line: &str, output: &mut W) -> Result<bool> {
+    let mut args = line.split_whitespace();
+    let command = match args.next() {
+        Some(command) => command,
+        None => return Ok(true),
+    };
+
+    if matches!(command, "back" | "rewind" | "who") && !debugger.has_history() {
+        return Err(anyhow!(
+            "No history, start the debugger with history enabled"
+        ));
+    }
+
+    match command {
+        "s" | "step" => {
+            let n = args.next().map_or(Ok(1), |n| parse_arg(Some(n)))?;
+            let mut event = Event::Stepped;
+            for _ in 0..n {
+                event = debugger.step()?;
+                if event != Event::Stepped {
+                    break;
+                }
+            }
+            writeln!(output, "{}", event)?;
+        }
+        "c" | "continue" => writeln!(output, "{}", debugger.cont()?)?,
+        "back" => {
+            let n = args.next().map_or(Ok(1), |n| parse_arg(Some(n)))?;
+            writeln!(output, "Rewound {} steps", debugger.step_back(n))?;
+        }
+        "rewind" => {
+            let addr = parse_arg(args.next())?;
+            match debugger.rewind_to_write(addr) {
+                Some(pc) => writeln!(output, "Rewound to write by {}", pc)?,
+                None => return Err(anyhow!("No recorded write to {}", addr)),
+            }
+        }
+        "who" => {
+            let addr = parse_arg(args.next())?;
+            match debugger.last_writer(addr) {
+                Some(pc) => writeln!(output, "{} was last written by {}", addr, pc)?,
+                None => writeln!(output, "{} has not been written", addr)?,
+            }
+        }
+        "b" | "break" => {
+            debugger.add_breakpoint(parse_arg(args.next())?);
+        }
+        "w" | "watch" => {
+            debugger.add_watchpoint(parse_arg(args.next())?);
+        }
+        "d" | "delete" => {
+            let addr = parse_arg(args.next())?;
+            debugger.remove_breakpoint(addr);
+            debugger.remove_watchpoint(addr);
+        }
+        "i" | "input" => {
+            for value in args {
+                debugger.feed(parse_arg(Some(value))?);
+            }
+        }
+        "r" | "regs" => writeln!(
+            output,
+            "pc={} rel_base={}",
+            debugger.pc(),
+            debugger.rel_base()
+        )?,
+        "m" | "mem" => {
+            let start = parse_arg(args.next())?;
+            let len = args.next().map_or(Ok(1), |n| parse_arg(Some(n)))?;
+            for (i, value) in debugger.memory(start, len).into_iter().enumerate() {
+                writeln!(output, "{:04}: {}", start + i, value)?;
+            }
+        }
+        "h" | "help" => write!(output, "{}", HELP)?,
+        "q" | "quit" => return Ok(false),
+        command => return Err(anyhow!("Unknown command {:?}", command)),
+    }
+    Ok(true)
+}
+
+pub fn repl<R: BufRead, W: Write>(debugger: &mut Debugger, input: R, mut output: W) -> Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        match execute(debugger, &line, &mut output) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => writeln!(output, "Error: {}", e)?,
+        }
+        if !debugger.is_halted() {
+            writeln!(output, "{:04}: {}", debugger.pc(), debugger.current())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    const PROGRAM: &str = "
+        in x
+        mul x, #2, y
+        out y
+        add y, #1, y
+        out y
+        hlt
+        x: data 0
+        y: data 0
+    ";
+
+    #[test]
+    fn test_step_and_continue() -> Result<()> {
+        let mut debugger = Debugger::new(assemble(PROGRAM)?);
+        assert_eq!(debugger.step()?, Event::Input(15));
+        assert_eq!(debugger.cont()?, Event::Input(15));
+        debugger.feed(21);
+        assert_eq!(debugger.step()?, Event::Stepped);
+        assert_eq!(debugger.memory(15, 2), vec![21, 0]);
+        assert_eq!(debugger.memory(16, 10), vec![0]);
+        assert_eq!(debugger.memory(usize::MAX, usize::MAX), vec![]);
+        assert_eq!(debugger.memory(0, usize::MAX).len(), 17);
+        assert_eq!(debugger.cont()?, Event::Output(42));
+        assert_eq!(debugger.cont()?, Event::Output(43));
+        assert_eq!(debugger.cont()?, Event::Halt);
+        assert_eq!(debugger.step()?, Event::Halt);
+        Ok(())
+    }
+
+    #[test]
+    fn test_breakpoints_and_watchpoints() -> Result<()> {
+        let mut debugger = Debugger::new(assemble(PROGRAM)?);
+        debugger.feed(5);
+        debugger.add_breakpoint(8);
+        debugger.add_watchpoint(16);
+        assert_eq!(
+            debugger.cont()?,
+            Event::Watchpoint {
+                addr: 16,
+                old: 0,
+                new: 10
+            }
+        );
+        assert_eq!(debugger.pc(), 6);
+        assert_eq!(debugger.cont()?, Event::Output(10));
+        assert_eq!(debugger.pc(), 8);
+        debugger.remove_watchpoint(16);
+        assert_eq!(debugger.cont()?, Event::Breakpoint(8));
+        assert_eq!(debugger.cont()?, Event::Output(11));
+        Ok(())
+    }
+
+    #[test]
+    fn test_breakpoint_at_start() -> Result<()> {
+        let mut debugger = Debugger::new(assemble(PROGRAM)?);
+        debugger.feed(1);
+        debugger.add_breakpoint(0);
+        assert_eq!(debugger.cont()?, Event::Breakpoint(0));
+        assert_eq!(debugger.pc(), 0);
+        assert_eq!(debugger.cont()?, Event::Output(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reverse() -> Result<()> {
+        let mut debugger = Debugger::new(assemble(PROGRAM)?).with_history();
+        debugger.feed(21);
+        assert_eq!(debugger.cont()?, Event::Output(42));
+        assert_eq!(debugger.last_writer(16), Some(2));
+        assert_eq!(debugger.last_writer(17), None);
+
+        assert_eq!(debugger.rewind_to_write(16), Some(2));
+        assert_eq!(debugger.pc(), 2);
+        assert_eq!(debugger.memory(15, 2), vec![21, 0]);
+
+        assert_eq!(debugger.step_back(5), 1);
+        assert_eq!(debugger.pc(), 0);
+        assert_eq!(debugger.memory(15, 2), vec![0, 0]);
+        assert_eq!(debugger.cont()?, Event::Output(42));
+
+        assert_eq!(debugger.cont()?, Event::Output(43));
+        assert_eq!(debugger.cont()?, Event::Halt);
+        assert_eq!(debugger.step_back(1), 1);
+        assert!(!debugger.is_halted());
+        assert_eq!(debugger.pc(), 14);
+        Ok(())
+    }
+
+    #[test]
+    fn test_repl() -> Result<()> {
+        let mut debugger = Debugger::new(assemble(PROGRAM)?);
+        let commands = "regs\ninput 4\nbreak 6\ncontinue\nmem 15 2\nfoo\nback\nc\nquit\n";
+        let mut output = Vec::new();
+        repl(&mut debugger, commands.as_bytes(), &mut output)?;
+        assert_eq!(
+            String::from_utf8(output)?,
+            [
+                "pc=0 rel_base=0",
+                "0000: in 15",
+                "0000: in 15",
+                "0000: in 15",
+                "Breakpoint at 6",
+                "0006: out 16",
+                "0015: 4",
+                "0016: 8",
+                "0006: out 16",
+                "Error: Unknown command \"foo\"",
+                "0006: out 16",
+                "Error: No history, start the debugger with history enabled",
+                "0006: out 16",
+                "Output 8",
+                "0008: add 16, #1, 16",
+                "",
+            ]
+            .join("\n")
+        );
+        Ok(())
+    }
+}
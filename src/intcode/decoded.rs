@@ -0,0 +1,337 @@
+use std::convert::TryInto;
+use std::fmt;
+
+use super::{IntcodeError, Interpreter, Memory, Mode, Opcode, ParamKind, Step, Word};
+
+/// Instructions starting beyond this address are decoded on every execution
+const MAX_CACHED_PC: usize = 1 << 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operand<W> {
+    Pos(usize),
+    Immediate(W),
+    Relative(W),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op<W> {
+    Add(Operand<W>, Operand<W>, Operand<W>),
+    Mul(Operand<W>, Operand<W>, Operand<W>),
+    In(Operand<W>),
+    Out(Operand<W>),
+    Jump(bool, Operand<W>, Operand<W>),
+    LessThan(Operand<W>, Operand<W>, Operand<W>),
+    Equal(Operand<W>, Operand<W>, Operand<W>),
+    Arb(Operand<W>),
+    Halt,
+}
+
+/// Decoded instructions by address. Entries are dropped when a write lands
+/// anywhere inside the instruction. The cache is kept on the interpreter so it
+/// survives pauses for input and output, but it never makes two interpreters
+/// compare differently.
+#[derive(Clone, Default)]
+pub(super) struct DecodeCache<W> {
+    ops: Vec<Option<Op<W>>>,
+}
+
+impl<W> PartialEq for DecodeCache<W> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<W> Eq for DecodeCache<W> {}
+
+impl<W> fmt::Debug for DecodeCache<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cached = self.ops.iter().filter(|op| op.is_some()).count();
+        write!(f, "DecodeCache({} instructions)", cached)
+    }
+}
+
+impl<W: Word> DecodeCache<W> {
+    pub(super) fn new() -> Self {
+        Self { ops: Vec::new() }
+    }
+
+    fn get(&self, pc: usize) -> Option<Op<W>> {
+        self.ops.get(pc).cloned().flatten()
+    }
+
+    fn insert(&mut self, pc: usize, op: Op<W>) {
+        if pc < MAX_CACHED_PC {
+            if self.ops.len() <= pc {
+                self.ops.resize(pc + 1, None);
+            }
+            self.ops[pc] = Some(op);
+        }
+    }
+
+    pub(super) fn invalidate(&mut self, addr: usize) {
+        for pc in addr.saturating_sub(3)..=addr {
+            if let Some(op) = self.ops.get_mut(pc) {
+                *op = None;
+            }
+        }
+    }
+}
+
+/// Decode the instruction at `pc`. Anything the fast path can't execute, like
+/// extension opcodes or instructions that will fail, is left to `step`.
+fn decode<M: Memory>(memory: &M, pc: usize) -> Option<Op<M::Word>> {
+    let op = Opcode::new(memory.get(pc).to_isize()?.try_into().ok()?);
+    let info = op.info()?;
+
+    let mut operands = Vec::with_capacity(3);
+    for (i, kind) in info.params.iter().enumerate() {
+        let value = memory.get(pc + i + 1);
+        operands.push(match (op.param_mode(i as u32)?, kind) {
+            (Mode::Pos, _) => Operand::Pos(value.to_isize()?.try_into().ok()?),
+            (Mode::Immediate, ParamKind::Input) => Operand::Immediate(value),
+            (Mode::Immediate, ParamKind::Output) => return None,
+            (Mode::Relative, _) => Operand::Relative(value),
+        });
+    }
+
+    let param = |i: usize| operands[i];
+    Some(match op.code() {
+        1 => Op::Add(param(0), param(1), param(2)),
+        2 => Op::Mul(param(0), param(1), param(2)),
+        3 => Op::In(param(0)),
+        4 => Op::Out(param(0)),
+        5 => Op::Jump(true, param(0), param(1)),
+        6 => Op::Jump(false, param(0), param(1)),
+        7 => Op::LessThan(param(0), param(1), param(2)),
+        8 => Op::Equal(param(0), param(1), param(2)),
+        9 => Op::Arb(param(0)),
+        99 => Op::Halt,
+        _ => return None,
+    })
+}
+
+impl<M: Memory> Interpreter<M> {
+    fn relative(&self, offset: M::Word) -> Option<usize> {
+        let addr = self.sum(self.rel_base, offset).ok()?;
+        self.address(addr, 0).ok()
+    }
+
+    fn load(&self, operand: Operand<M::Word>) -> Option<M::Word> {
+        match operand {
+            Operand::Pos(addr) => Some(self.get(addr)),
+            Operand::Immediate(value) => Some(value),
+            Operand::Relative(offset) => Some(self.get(self.relative(offset)?)),
+        }
+    }
+
+    fn target(&self, operand: Operand<M::Word>) -> Option<usize> {
+        match operand {
+            Operand::Pos(addr) => Some(addr),
+            Operand::Immediate(_) => None,
+            Operand::Relative(offset) => self.relative(offset),
+        }
+    }
+
+    /// Execute a decoded instruction. Returns `None` without changing any
+    /// state when the instruction would fail.
+    fn execute_decoded(&mut self, pc: usize, op: Op<M::Word>) -> Option<Step<M::Word>> {
+        self.op_pc = pc;
+        match op {
+            Op::Add(a, b, c) | Op::Mul(a, b, c) | Op::LessThan(a, b, c) | Op::Equal(a, b, c) => {
+                let (a, b) = (self.load(a)?, self.load(b)?);
+                let addr = self.target(c)?;
+                let value = match op {
+                    Op::Add(..) => self.sum(a, b).ok()?,
+                    Op::Mul(..) => self.product(a, b).ok()?,
+                    Op::LessThan(..) if a < b => M::Word::ONE,
+                    Op::Equal(..) if a == b => M::Word::ONE,
+                    _ => M::Word::ZERO,
+                };
+                self.put(addr, value);
+                self.pc = pc + 4;
+            }
+            Op::In(a) => {
+                let addr = self.target(a)?;
+                self.pc = pc + 2;
+                return Some(Step::Input(addr));
+            }
+            Op::Out(a) => {
+                let value = self.load(a)?;
+                self.pc = pc + 2;
+                return Some(Step::Output(value));
+            }
+            Op::Jump(if_true, a, b) => {
+                let (cond, target) = (self.load(a)?, self.load(b)?);
+                self.pc = if (cond != M::Word::ZERO) == if_true {
+                    self.address(target, 1).ok()?
+                } else {
+                    pc + 3
+                };
+            }
+            Op::Arb(a) => {
+                self.rel_base = self.sum(self.rel_base, self.load(a)?).ok()?;
+                self.pc = pc + 2;
+            }
+            Op::Halt => {
+                self.pc = pc + 1;
+                return Some(Step::Halt);
+            }
+        }
+        Some(Step::Continue)
+    }
+
+    pub(super) fn step_decoded(&mut self) -> Result<Step<M::Word>, IntcodeError> {
+        let pc = self.pc;
+        let op = match self.decoded.get(pc) {
+            Some(op) => Some(op),
+            None => decode(&self.memory, pc).map(|op| {
+                self.decoded.insert(pc, op);
+                op
+            }),
+        };
+
+        if let Some(step) = op.and_then(|op| self.execute_decoded(pc, op)) {
+            return Ok(step);
+        }
+
+        // Extension handlers write through `put`, which keeps the cache valid
+        self.step()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+    use crate::intcode::{Engine, State};
+    use anyhow::Result;
+
+    fn with_engine(state: State, engine: Engine) -> State {
+        match state {
+            State::Input(mut c) => {
+                c.inner.engine = engine;
+                State::Input(c)
+            }
+            State::Output(mut c) => {
+                c.inner.engine = engine;
+                State::Output(c)
+            }
+            State::Limit(mut c) => {
+                c.inner.engine = engine;
+                State::Limit(c)
+            }
+            State::Halt(memory) => State::Halt(memory),
+        }
+    }
+
+    /// Run both engines side by side and compare every state they pause in
+    fn differential(computer: Interpreter, input: &[isize]) -> Result<usize> {
+        let mut decoded = computer.clone();
+        decoded.set_engine(Engine::Decoded);
+
+        let mut input = input.iter().cloned();
+        let mut expected = computer.run();
+        let mut actual = decoded
+            .run()
+            .map(|state| with_engine(state, Engine::Standard));
+        let mut pauses = 0;
+        loop {
+            assert_eq!(actual, expected);
+            pauses += 1;
+
+            let (a, b) = match (expected, actual) {
+                (Ok(State::Input(a)), Ok(State::Input(b))) => match input.next() {
+                    Some(value) => (a.resume(value), b.resume(value)),
+                    None => return Ok(pauses),
+                },
+                (Ok(State::Output(a)), Ok(State::Output(b))) => (a.resume(), b.resume()),
+                _ => return Ok(pauses),
+            };
+            expected = a;
+            actual = b;
+        }
+    }
+
+    #[test]
+    fn test_puzzle_inputs() -> Result<()> {
+        let mut programs = 0;
+        for entry in std::fs::read_dir("data")? {
+            // Not every input is an Intcode program
+            let computer = match Interpreter::from_path(entry?.path()) {
+                Ok(computer) => computer,
+                Err(_) => continue,
+            };
+            programs += 1;
+
+            for input in &[vec![], vec![0], vec![1], vec![2], vec![5], vec![8, 9]] {
+                differential(computer.clone(), input)?;
+            }
+
+            for (noun, verb) in &[(12, 2), (0, 0), (99, 99), (-1, 3)] {
+                let mut computer = computer.clone();
+                computer.put(1, *noun);
+                computer.put(2, *verb);
+                differential(computer, &[1, 5])?;
+            }
+        }
+        assert!(programs >= 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        for program in &[
+            vec![1101, 1, 1, 5, 42],
+            vec![301, 0, 0, 0],
+            vec![1, 0, -3, 0],
+            vec![1105, 1, -7],
+            vec![109, -5, 204, 1],
+            vec![1102, isize::MAX, 2, 5, 99, 0],
+        ] {
+            differential(Interpreter::from_iter(program.clone()), &[])?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_self_modifying() -> Result<()> {
+        // Patches its own add into a mul after the second iteration
+        let program = "
+            loop: out v
+            ins:  add v, #2, v
+                  add n, #-1, n
+                  eq n, #2, t
+                  add ins, t, ins
+                  jnz n, #loop
+                  hlt
+            v:    data 1
+            n:    data 4
+            t:    data 0
+        ";
+        assert_eq!(differential(assemble(program)?, &[])?, 5);
+
+        let mut computer = assemble(program)?;
+        computer.set_engine(Engine::Decoded);
+        let output = computer.run_with(vec![]).collect::<Result<Vec<_>>>()?;
+        assert_eq!(output, vec![1, 3, 5, 10]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_across_pauses() -> Result<()> {
+        let mut computer = assemble("loop: in x\nout x\njnz #1, #loop\nx: data 0")?;
+        computer.set_engine(Engine::Decoded);
+        let c = computer.run()?.expect_input().resume(3)?.expect_output();
+        assert_eq!(c.get(), 3);
+        assert!(c.inner.decoded.get(0).is_some());
+        assert!(c.inner.decoded.get(2).is_some());
+
+        // Turn the output into an immediate one
+        let mut computer = c.into_interpreter();
+        computer.put(2, 104);
+        assert!(computer.decoded.get(2).is_none());
+        let c = computer.run()?.expect_input().resume(5)?.expect_output();
+        assert_eq!(c.get(), 7);
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+use std::convert::TryInto;
+use std::fmt;
+
+use super::{Interpreter, Memory, Mode, Opcode, ParamKind};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    Instruction {
+        opcode: Opcode,
+        mnemonic: &'static str,
+        operands: Vec<(Mode, isize)>,
+    },
+    Data(isize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    pub addr: usize,
+    pub words: Vec<isize>,
+    pub label: Option<String>,
+    pub item: Item,
+}
+
+fn label_name(addr: usize) -> String {
+    format!("L{:04}", addr)
+}
+
+pub fn decode<M: Memory<Word = isize>>(computer: &Interpreter<M>, addr: usize) -> Option<Item> {
+    let opcode = Opcode::new(computer.get(addr).try_into().ok()?);
+    let info = opcode.info()?;
+
+    // Mode digits beyond the last parameter would be lost when reassembling
+    if opcode.inner / 10usize.pow(info.params.len() as u32 + 2) != 0 {
+        return None;
+    }
+
+    let mut operands = Vec::new();
+    for (i, kind) in info.params.iter().enumerate() {
+        let mode = opcode.param_mode(i as u32)?;
+        if mode == Mode::Immediate && *kind == ParamKind::Output {
+            return None;
+        }
+        operands.push((mode, computer.get(addr + i + 1)));
+    }
+
+    Some(Item::Instruction {
+        opcode,
+        mnemonic: info.mnemonic,
+        operands,
+    })
+}
+
+fn jump_target(item: &Item) -> Option<usize> {
+    match item {
+        Item::Instruction {
+            opcode, operands, ..
+        } if opcode.code() == 5 || opcode.code() == 6 => match operands[1] {
+            (Mode::Immediate, target) => target.try_into().ok(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub fn disassemble<M: Memory<Word = isize>>(computer: &Interpreter<M>) -> Vec<Line> {
+    let size = computer.memory_size();
+    let mut lines = Vec::new();
+    let mut addr = 0;
+
+    while addr < size {
+        let item = match decode(computer, addr) {
+            Some(Item::Instruction { operands, .. }) if addr + operands.len() >= size => {
+                Item::Data(computer.get(addr))
+            }
+            Some(item) => item,
+            None => Item::Data(computer.get(addr)),
+        };
+        let len = match &item {
+            Item::Instruction { operands, .. } => operands.len() + 1,
+            Item::Data(_) => 1,
+        };
+
+        lines.push(Line {
+            addr,
+            words: (addr..addr + len).map(|i| computer.get(i)).collect(),
+            label: None,
+            item,
+        });
+        addr += len;
+    }
+
+    let starts: HashSet<_> = lines.iter().map(|line| line.addr).collect();
+    let targets: HashSet<_> = lines
+        .iter()
+        .filter_map(|line| jump_target(&line.item))
+        .filter(|target| starts.contains(target))
+        .collect();
+
+    for line in lines.iter_mut() {
+        if targets.contains(&line.addr) {
+            line.label = Some(label_name(line.addr));
+        }
+    }
+    lines
+}
+
+fn render(item: &Item, target_label: Option<String>) -> String {
+    match item {
+        Item::Instruction {
+            mnemonic, operands, ..
+        } => {
+            let operands = operands
+                .iter()
+                .enumerate()
+                .map(|(i, (mode, value))| {
+                    let sigil = match mode {
+                        Mode::Pos => "",
+                        Mode::Immediate => "#",
+                        Mode::Relative => "@",
+                    };
+                    match &target_label {
+                        Some(label) if i == 1 => format!("{}{}", sigil, label),
+                        _ => format!("{}{}", sigil, value),
+                    }
+                })
+                .collect::<Vec<_>>();
+            format!("{} {}", mnemonic, operands.join(", "))
+                .trim_end()
+                .to_owned()
+        }
+        Item::Data(value) => format!("data {}", value),
+    }
+}
+
+pub fn listing<M: Memory<Word = isize>>(computer: &Interpreter<M>) -> String {
+    let lines = disassemble(computer);
+    let labels: HashSet<_> = lines
+        .iter()
+        .filter(|line| line.label.is_some())
+        .map(|line| line.addr)
+        .collect();
+
+    lines
+        .iter()
+        .map(|line| {
+            let target = jump_target(&line.item)
+                .filter(|target| labels.contains(target))
+                .map(label_name);
+            let label = line
+                .label
+                .as_ref()
+                .map(|label| format!("{}:", label))
+                .unwrap_or_default();
+            format!(
+                "{:<8}{:<31} ; {}\n",
+                label,
+                render(&line.item, target),
+                line
+            )
+        })
+        .collect()
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", render(self, None))
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let words: Vec<_> = self.words.iter().map(|w| w.to_string()).collect();
+        write!(f, "{:04}: {}", self.addr, words.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::compile;
+    use anyhow::Result;
+
+    #[test]
+    fn test_disassemble() {
+        let lines = disassemble(&Interpreter::from_iter(vec![1002, 4, 3, 4, 33, -1]));
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0].item,
+            Item::Instruction {
+                opcode: Opcode::new(1002),
+                mnemonic: "mul",
+                operands: vec![(Mode::Pos, 4), (Mode::Immediate, 3), (Mode::Pos, 4)],
+            }
+        );
+        assert_eq!(lines[1].item, Item::Data(33));
+        assert_eq!(lines[2].item, Item::Data(-1));
+    }
+
+    #[test]
+    fn test_invalid_instructions_are_data() {
+        // Immediate output, invalid mode, stray mode digits and truncated operands
+        let lines = disassemble(&Interpreter::from_iter(vec![11101, 301, 10099, 1]));
+        let items: Vec<_> = lines.into_iter().map(|line| line.item).collect();
+        assert_eq!(
+            items,
+            vec![
+                Item::Data(11101),
+                Item::Data(301),
+                Item::Data(10099),
+                Item::Data(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_listing() -> Result<()> {
+        let source = "start: in 10\njz 10, #start\nout #1\nhlt";
+        let listing = listing(&Interpreter::from_iter(compile(source)?));
+        assert_eq!(
+            listing,
+            [
+                "L0000:  in 10                           ; 0000: 3,10\n",
+                "        jz 10, #L0000                   ; 0002: 1006,10,0\n",
+                "        out #1                          ; 0005: 104,1\n",
+                "        hlt                             ; 0007: 99\n",
+            ]
+            .concat()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        for path in &["data/day2.txt", "data/day5.txt", "data/day9.txt"] {
+            let computer = Interpreter::from_path(path)?;
+            let words = compile(&listing(&computer))?;
+            assert_eq!(Interpreter::from_iter(words), computer);
+        }
+        Ok(())
+    }
+}
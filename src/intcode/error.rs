@@ -0,0 +1,120 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntcodeError {
+    InvalidOpcode {
+        pc: usize,
+        opcode: i128,
+    },
+    InvalidMode {
+        pc: usize,
+        opcode: i128,
+        param: usize,
+        mode: usize,
+    },
+    ImmediateOutput {
+        pc: usize,
+        opcode: i128,
+        param: usize,
+    },
+    NegativeAddress {
+        pc: usize,
+        opcode: i128,
+        param: usize,
+        address: i128,
+    },
+    AddressOutOfRange {
+        pc: usize,
+        opcode: i128,
+        param: usize,
+        address: i128,
+    },
+    Overflow {
+        pc: usize,
+        opcode: i128,
+        lhs: i128,
+        rhs: i128,
+    },
+}
+
+impl IntcodeError {
+    pub fn pc(&self) -> usize {
+        match *self {
+            IntcodeError::InvalidOpcode { pc, .. }
+            | IntcodeError::InvalidMode { pc, .. }
+            | IntcodeError::ImmediateOutput { pc, .. }
+            | IntcodeError::NegativeAddress { pc, .. }
+            | IntcodeError::AddressOutOfRange { pc, .. }
+            | IntcodeError::Overflow { pc, .. } => pc,
+        }
+    }
+
+    pub fn opcode(&self) -> i128 {
+        match *self {
+            IntcodeError::InvalidOpcode { opcode, .. }
+            | IntcodeError::InvalidMode { opcode, .. }
+            | IntcodeError::ImmediateOutput { opcode, .. }
+            | IntcodeError::NegativeAddress { opcode, .. }
+            | IntcodeError::AddressOutOfRange { opcode, .. }
+            | IntcodeError::Overflow { opcode, .. } => opcode,
+        }
+    }
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::InvalidOpcode { pc, opcode } => {
+                write!(f, "Got invalid opcode {} at address {}", opcode, pc)
+            }
+            IntcodeError::InvalidMode {
+                pc,
+                opcode,
+                param,
+                mode,
+            } => write!(
+                f,
+                "Invalid parameter mode {} for parameter {} of opcode {} at address {}",
+                mode, param, opcode, pc
+            ),
+            IntcodeError::ImmediateOutput { pc, opcode, param } => write!(
+                f,
+                "Output parameter {} of opcode {} at address {} must not be in immediate mode",
+                param, opcode, pc
+            ),
+            IntcodeError::NegativeAddress {
+                pc,
+                opcode,
+                param,
+                address,
+            } => write!(
+                f,
+                "Negative address {} for parameter {} of opcode {} at address {}",
+                address, param, opcode, pc
+            ),
+            IntcodeError::AddressOutOfRange {
+                pc,
+                opcode,
+                param,
+                address,
+            } => write!(
+                f,
+                "Address {} for parameter {} of opcode {} at address {} is out of range",
+                address, param, opcode, pc
+            ),
+            IntcodeError::Overflow {
+                pc,
+                opcode,
+                lhs,
+                rhs,
+            } => write!(
+                f,
+                "Arithmetic overflow with operands {} and {} in opcode {} at address {}",
+                lhs, rhs, opcode, pc
+            ),
+        }
+    }
+}
+
+impl Error for IntcodeError {}
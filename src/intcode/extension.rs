@@ -0,0 +1,131 @@
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use super::{IntcodeError, Interpreter, Memory, Opcode, Step, INSTRUCTIONS};
+
+pub type Handler<M> = Arc<
+    dyn Fn(&mut Interpreter<M>, Opcode) -> Result<Step<<M as Memory>::Word>, IntcodeError>
+        + Send
+        + Sync,
+>;
+
+/// Handlers for opcodes that are not part of the base instruction set
+#[derive(Clone)]
+pub struct Extensions<M: Memory> {
+    handlers: BTreeMap<usize, Handler<M>>,
+}
+
+impl<M: Memory> Extensions<M> {
+    pub fn new() -> Self {
+        Self {
+            handlers: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self, code: usize) -> Option<&Handler<M>> {
+        self.handlers.get(&code)
+    }
+
+    pub fn codes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.handlers.keys().cloned()
+    }
+
+    pub fn register(&mut self, code: usize, handler: Handler<M>) -> Result<()> {
+        if code >= 100 {
+            return Err(anyhow!("Opcode {} does not fit in two digits", code));
+        }
+        if let Some(info) = INSTRUCTIONS.iter().find(|info| info.code == code) {
+            return Err(anyhow!(
+                "Opcode {} is already used by built-in instruction {}",
+                code,
+                info.mnemonic
+            ));
+        }
+        if self.handlers.contains_key(&code) {
+            return Err(anyhow!("Opcode {} is already registered", code));
+        }
+        self.handlers.insert(code, handler);
+        Ok(())
+    }
+}
+
+impl<M: Memory> Default for Extensions<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Memory> fmt::Debug for Extensions<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.codes()).finish()
+    }
+}
+
+impl<M: Memory> PartialEq for Extensions<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.handlers.len() == other.handlers.len()
+            && self
+                .handlers
+                .iter()
+                .zip(other.handlers.iter())
+                .all(|((a, f), (b, g))| a == b && Arc::ptr_eq(f, g))
+    }
+}
+
+impl<M: Memory> Eq for Extensions<M> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Opcode 20 squares its first parameter into its second
+    fn square(computer: &mut Interpreter, op: Opcode) -> Result<Step, IntcodeError> {
+        let value = computer.read_input_param(computer.param_mode(op, 0)?)?;
+        let output = computer.read_output_param(computer.param_mode(op, 1)?)?;
+        computer.write(output, value * value);
+        Ok(Step::Continue)
+    }
+
+    #[test]
+    fn test_extension() -> Result<()> {
+        let mut computer = Interpreter::from_iter(vec![20, 9, 10, 120, 12, 11, 99, 0, 0, 12]);
+        computer.register_opcode(20, square)?;
+
+        let memory = computer.run()?.expect_halt();
+        assert_eq!(memory.get(10), 144);
+        assert_eq!(memory.get(11), 144);
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_call() -> Result<()> {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        // Opcode 42 asks the host for the number of calls made so far
+        let mut computer = Interpreter::from_iter(vec![42, 10, 42, 11, 1, 10, 11, 12, 99]);
+        computer.register_opcode(42, move |computer: &mut Interpreter, op| {
+            let output = computer.read_output_param(computer.param_mode(op, 0)?)?;
+            let value = counter.fetch_add(1, Ordering::SeqCst) as isize + 1;
+            computer.write(output, value);
+            Ok(Step::Continue)
+        })?;
+
+        assert_eq!(computer.run()?.expect_halt().get(12), 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut computer = Interpreter::from_iter(vec![99]);
+        assert!(computer.register_opcode(1, square).is_err());
+        assert!(computer.register_opcode(99, square).is_err());
+        assert!(computer.register_opcode(100, square).is_err());
+        assert!(computer.register_opcode(20, square).is_ok());
+        assert!(computer.register_opcode(20, square).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use super::trace::MemoryWrite;
+use super::{Interpreter, Memory};
+
+/// State before a single executed instruction along with what it overwrote
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry<W = isize> {
+    pub pc: usize,
+    pub opcode: W,
+    pub rel_base: W,
+    pub writes: Vec<MemoryWrite<W>>,
+}
+
+/// Undo log of executed instructions, oldest first. Input written while an
+/// input instruction is paused belongs to that instruction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct History<W = isize> {
+    entries: Vec<HistoryEntry<W>>,
+}
+
+impl<W: Copy> History<W> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry<W>] {
+        &self.entries
+    }
+
+    /// The most recent write to `addr` along with the index of the step that
+    /// made it
+    pub fn last_write(&self, addr: usize) -> Option<(usize, &HistoryEntry<W>, MemoryWrite<W>)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, entry)| {
+                let write = entry.writes.iter().rev().find(|write| write.addr == addr)?;
+                Some((i, entry, *write))
+            })
+    }
+
+    pub(crate) fn push(&mut self, pc: usize, opcode: W, rel_base: W) {
+        self.entries.push(HistoryEntry {
+            pc,
+            opcode,
+            rel_base,
+            writes: Vec::new(),
+        });
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<HistoryEntry<W>> {
+        self.entries.pop()
+    }
+
+    pub(crate) fn record_write(&mut self, addr: usize, old: W, new: W) {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.writes.push(MemoryWrite { addr, old, new });
+        }
+    }
+}
+
+impl<M: Memory> Interpreter<M> {
+    pub fn enable_history(&mut self) {
+        self.history.get_or_insert_with(History::new);
+    }
+
+    pub fn disable_history(&mut self) -> Option<History<M::Word>> {
+        self.history.take()
+    }
+
+    pub fn history(&self) -> Option<&History<M::Word>> {
+        self.history.as_ref()
+    }
+
+    /// Undo the most recently executed instruction. Limits, traces and
+    /// profiles are not rewound.
+    pub fn step_back(&mut self) -> Option<HistoryEntry<M::Word>> {
+        let entry = self.history.as_mut()?.pop()?;
+        for write in entry.writes.iter().rev() {
+            self.put(write.addr, write.old);
+        }
+        self.pc = entry.pc;
+        self.op_pc = entry.pc;
+        self.rel_base = entry.rel_base;
+        Some(entry)
+    }
+
+    /// Undo up to `n` instructions, returning how many were undone
+    pub fn rewind(&mut self, n: usize) -> usize {
+        (0..n).take_while(|_| self.step_back().is_some()).count()
+    }
+
+    /// Rewind to just before the instruction that last wrote to `addr`, so
+    /// that stepping once repeats the write. Nothing is undone if there is no
+    /// such write in the history.
+    pub fn rewind_to_write(&mut self, addr: usize) -> Option<HistoryEntry<M::Word>> {
+        let (step, _, _) = self.history.as_ref()?.last_write(addr)?;
+        let undo = self.history.as_ref()?.len() - step;
+        self.rewind(undo - 1);
+        self.step_back()
+    }
+
+    /// Address of the instruction that last wrote to `addr`
+    pub fn last_writer(&self, addr: usize) -> Option<usize> {
+        let (_, entry, _) = self.history.as_ref()?.last_write(addr)?;
+        Some(entry.pc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+    use crate::intcode::Step;
+    use anyhow::Result;
+
+    const PROGRAM: &str = "
+        in x
+        mul x, #2, y
+        arb #3
+        add y, #1, y
+        out y
+        hlt
+        x: data 0
+        y: data 0
+    ";
+
+    fn run_to_halt(computer: &mut Interpreter, input: isize) -> Result<()> {
+        loop {
+            match computer.step()? {
+                Step::Input(pos) => computer.write(pos, input),
+                Step::Halt => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_step_back() -> Result<()> {
+        let mut computer = assemble(PROGRAM)?;
+        computer.enable_history();
+        let original = computer.clone();
+        run_to_halt(&mut computer, 7)?;
+        assert_eq!(computer.history().map(History::len), Some(6));
+        assert_eq!(computer.get(16), 15);
+
+        let entry = computer.step_back().unwrap();
+        assert_eq!(entry.pc, 14);
+        assert_eq!(computer.pc(), 14);
+
+        assert_eq!(computer.rewind(2), 2);
+        assert_eq!(computer.pc(), 8);
+        assert_eq!(computer.rel_base(), 3);
+        assert_eq!(computer.get(16), 14);
+
+        assert_eq!(computer.rewind(10), 3);
+        assert_eq!(computer.pc(), 0);
+        assert_eq!(computer.rel_base(), 0);
+        assert_eq!(computer.memory(), original.memory());
+        assert!(computer.step_back().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_write() -> Result<()> {
+        let mut computer = assemble(PROGRAM)?;
+        computer.enable_history();
+        run_to_halt(&mut computer, 7)?;
+
+        assert_eq!(computer.last_writer(15), Some(0));
+        assert_eq!(computer.last_writer(16), Some(8));
+        assert_eq!(computer.last_writer(0), None);
+
+        let (step, _, write) = computer.history().unwrap().last_write(16).unwrap();
+        assert_eq!(step, 3);
+        assert_eq!(
+            write,
+            MemoryWrite {
+                addr: 16,
+                old: 14,
+                new: 15
+            }
+        );
+
+        let entry = computer.rewind_to_write(16).unwrap();
+        assert_eq!(entry.pc, 8);
+        assert_eq!(computer.pc(), 8);
+        assert_eq!(computer.get(16), 14);
+        assert_eq!(computer.last_writer(16), Some(2));
+
+        assert!(computer.rewind_to_write(0).is_none());
+        assert_eq!(computer.pc(), 8);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rewind_after_output() -> Result<()> {
+        let mut computer = assemble(PROGRAM)?;
+        computer.enable_history();
+        let c = computer.run()?.expect_input().resume(4)?.expect_output();
+        assert_eq!(c.get(), 9);
+        let mut computer = c.into_interpreter();
+
+        assert_eq!(computer.last_writer(16), Some(8));
+        computer.rewind_to_write(15);
+        assert_eq!(computer.pc(), 0);
+        assert_eq!(computer.get(15), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::{anyhow, Result};
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use super::{Interpreter, Memory, PagedMemory, State, Step};
+
+enum Machine<M: Memory> {
+    Ready(Interpreter<M>),
+    Paused(State<M>),
+    Done,
+}
+
+pub struct Outputs<I, M: Memory = PagedMemory> {
+    machine: Machine<M>,
+    input: I,
+}
+
+pub(crate) fn next_input<I: Iterator>(input: &mut I) -> Result<I::Item> {
+    input
+        .next()
+        .ok_or_else(|| anyhow!("Program requested input but none is left"))
+}
+
+impl<M: Memory> Interpreter<M> {
+    /// Execute one instruction, answering a request for input with the next
+    /// value from `input`
+    pub(crate) fn step_with<I: Iterator<Item = M::Word>>(
+        &mut self,
+        input: &mut I,
+    ) -> Result<Step<M::Word>> {
+        let step = self.step()?;
+        match step {
+            Step::Input(pos) => self.write(pos, next_input(input)?),
+            Step::Limit(limit) => return Err(anyhow!("{}", limit)),
+            Step::Continue | Step::Output(_) | Step::Halt => {}
+        }
+        Ok(step)
+    }
+
+    pub fn run_with<T: IntoIterator<Item = M::Word>>(self, input: T) -> Outputs<T::IntoIter, M> {
+        Outputs {
+            machine: Machine::Ready(self),
+            input: input.into_iter(),
+        }
+    }
+
+    pub fn spawn(self, input: Receiver<M::Word>, output: Sender<M::Word>) -> JoinHandle<Result<M>>
+    where
+        M: Send + 'static,
+    {
+        thread::spawn(move || {
+            let mut state = self.run()?;
+            loop {
+                state =
+                    match state {
+                        State::Input(c) => c.resume(input.recv().map_err(|_| {
+                            anyhow!("Input channel closed before program halted")
+                        })?)?,
+                        State::Output(c) => {
+                            output.send(c.get()).map_err(|_| {
+                                anyhow!("Output channel closed before program halted")
+                            })?;
+                            c.resume()?
+                        }
+                        State::Limit(c) => return Err(anyhow!("{}", c.limit())),
+                        State::Halt(memory) => return Ok(memory),
+                    }
+            }
+        })
+    }
+}
+
+impl<M: Memory, I: Iterator<Item = M::Word>> Outputs<I, M> {
+    fn advance(&mut self) -> Result<Option<M::Word>> {
+        let mut state = match std::mem::replace(&mut self.machine, Machine::Done) {
+            Machine::Ready(computer) => computer.run()?,
+            Machine::Paused(State::Output(c)) => c.resume()?,
+            Machine::Paused(state) => state,
+            Machine::Done => return Ok(None),
+        };
+
+        loop {
+            state = match state {
+                State::Input(c) => c.resume(next_input(&mut self.input)?)?,
+                State::Output(c) => {
+                    let value = c.get();
+                    self.machine = Machine::Paused(State::Output(c));
+                    return Ok(Some(value));
+                }
+                State::Limit(c) => return Err(anyhow!("{}", c.limit())),
+                State::Halt(memory) => {
+                    self.machine = Machine::Paused(State::Halt(memory));
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    pub fn memory(&self) -> Option<&M> {
+        match &self.machine {
+            Machine::Paused(State::Halt(memory)) => Some(memory),
+            _ => None,
+        }
+    }
+}
+
+impl<M: Memory, I: Iterator<Item = M::Word>> Iterator for Outputs<I, M> {
+    type Item = Result<M::Word>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.advance().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+    use std::sync::mpsc::channel;
+
+    const DOUBLER: &str = "
+        loop: in x
+              jz x, #end
+              mul x, #2, x
+              out x
+              jnz #1, #loop
+        end:  hlt
+        x:    data 0
+    ";
+
+    #[test]
+    fn test_run_with() -> Result<()> {
+        let mut outputs = assemble(DOUBLER)?.run_with(vec![1, 2, 3, 0]);
+        assert_eq!(outputs.next().transpose()?, Some(2));
+        assert!(outputs.memory().is_none());
+        assert_eq!(outputs.by_ref().collect::<Result<Vec<_>>>()?, vec![4, 6]);
+        assert_eq!(outputs.memory().map(|m| m.get(15)), Some(0));
+        assert!(outputs.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_with_missing_input() -> Result<()> {
+        let outputs = assemble(DOUBLER)?.run_with(vec![5]);
+        assert!(outputs.collect::<Result<Vec<_>>>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_spawn() -> Result<()> {
+        let (input_tx, input_rx) = channel();
+        let (middle_tx, middle_rx) = channel();
+        let (output_tx, output_rx) = channel();
+        let a = assemble(DOUBLER)?.spawn(input_rx, middle_tx);
+        let b = assemble(DOUBLER)?.spawn(middle_rx, output_tx);
+
+        for value in &[1, 5, -2] {
+            input_tx.send(*value)?;
+        }
+        assert_eq!(
+            output_rx.iter().take(3).collect::<Vec<_>>(),
+            vec![4, 20, -8]
+        );
+
+        // Stopping the first machine closes the input of the second one
+        input_tx.send(0)?;
+        assert!(a.join().unwrap().is_ok());
+        assert!(b.join().unwrap().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    max_instructions: Option<u64>,
+    max_cells: Option<usize>,
+    max_address: Option<usize>,
+    deadline: Option<Instant>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Instructions(u64),
+    Cells(usize),
+    Address(usize),
+    Deadline,
+}
+
+/// Usage tracking for an interpreter running with limits
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Budget {
+    pub limits: Limits,
+    pub instructions: u64,
+    pub touched: HashSet<usize>,
+    exceeded: Option<Limit>,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_instructions(mut self, max: u64) -> Self {
+        self.max_instructions = Some(max);
+        self
+    }
+
+    /// Maximum number of distinct memory cells read or written by parameters
+    pub fn max_cells(mut self, max: usize) -> Self {
+        self.max_cells = Some(max);
+        self
+    }
+
+    /// Highest address parameters may use or jumps may target
+    pub fn max_address(mut self, max: usize) -> Self {
+        self.max_address = Some(max);
+        self
+    }
+
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.deadline(Instant::now() + timeout)
+    }
+}
+
+impl Budget {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            instructions: 0,
+            touched: HashSet::new(),
+            exceeded: None,
+        }
+    }
+
+    /// Flag an address above the address limit, returning whether it is allowed
+    pub fn check_address(&mut self, addr: usize) -> bool {
+        if self.exceeded.is_some() {
+            return false;
+        }
+        match self.limits.max_address {
+            Some(max) if addr > max => {
+                self.exceeded = Some(Limit::Address(addr));
+                false
+            }
+            _ => true,
+        }
+    }
+
+    pub fn touch(&mut self, addr: usize) {
+        if !self.check_address(addr) {
+            return;
+        }
+
+        if let Some(max) = self.limits.max_cells {
+            if !self.touched.contains(&addr) {
+                if self.touched.len() >= max {
+                    self.exceeded = Some(Limit::Cells(max));
+                    return;
+                }
+                self.touched.insert(addr);
+            }
+        }
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        self.exceeded.is_some()
+    }
+
+    pub fn before_step(&mut self) -> Option<Limit> {
+        self.exceeded = None;
+        if let Some(max) = self.limits.max_instructions {
+            if self.instructions >= max {
+                return Some(Limit::Instructions(max));
+            }
+        }
+        match self.limits.deadline {
+            Some(deadline) if Instant::now() >= deadline => Some(Limit::Deadline),
+            _ => None,
+        }
+    }
+
+    pub fn after_step(&mut self) -> Option<Limit> {
+        let exceeded = self.exceeded.take();
+        if exceeded.is_none() {
+            self.instructions += 1;
+        }
+        exceeded
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::Instructions(max) => write!(f, "Executed the maximum of {} instructions", max),
+            Limit::Cells(max) => write!(f, "Touched the maximum of {} memory cells", max),
+            Limit::Address(addr) => write!(f, "Address {} is above the address limit", addr),
+            Limit::Deadline => write!(f, "Deadline exceeded"),
+        }
+    }
+}
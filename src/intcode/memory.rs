@@ -0,0 +1,221 @@
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::read_to_string;
+use std::iter::FromIterator;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::Word;
+
+const PAGE_BITS: usize = 10;
+const PAGE_SIZE: usize = 1 << PAGE_BITS;
+const DENSE_PAGES: usize = 1 << 12;
+
+type Page<W> = [W; PAGE_SIZE];
+
+pub trait Memory: Clone + fmt::Debug {
+    type Word: Word;
+
+    fn get(&self, addr: usize) -> Self::Word;
+    fn put(&mut self, addr: usize, value: Self::Word);
+
+    /// One past the highest address that has been written to
+    fn size(&self) -> usize;
+}
+
+/// Vector backed memory split into fixed size pages. Pages are shared between
+/// clones and only copied when written to, which makes forking a machine cheap.
+/// Addresses past the dense region are kept in a sparse map of pages.
+#[derive(Clone, Default)]
+pub struct PagedMemory<W = isize> {
+    dense: Vec<Option<Arc<Page<W>>>>,
+    sparse: BTreeMap<usize, Arc<Page<W>>>,
+    size: usize,
+}
+
+impl<W: Word> Memory for HashMap<usize, W> {
+    type Word = W;
+
+    fn get(&self, addr: usize) -> W {
+        *HashMap::get(self, &addr).unwrap_or(&W::ZERO)
+    }
+
+    fn put(&mut self, addr: usize, value: W) {
+        self.insert(addr, value);
+    }
+
+    fn size(&self) -> usize {
+        self.keys().max().map_or(0, |&i| i + 1)
+    }
+}
+
+impl<W: Word> PagedMemory<W> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a comma separated program
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let str_input = read_to_string(path)?;
+        let program = str_input
+            .trim_end()
+            .split(",")
+            .map(|x| x.parse())
+            .collect::<Result<Vec<W>, _>>()?;
+
+        if program.is_empty() {
+            return Err(anyhow!(
+                "Program is too short (expected at least 1 element, got 0)"
+            ));
+        }
+
+        Ok(program.into_iter().collect())
+    }
+
+    fn page(&self, index: usize) -> Option<&Page<W>> {
+        if index < DENSE_PAGES {
+            self.dense.get(index)?.as_deref()
+        } else {
+            self.sparse.get(&index).map(|page| &**page)
+        }
+    }
+
+    fn page_mut(&mut self, index: usize) -> &mut Page<W> {
+        let page = if index < DENSE_PAGES {
+            if self.dense.len() <= index {
+                self.dense.resize(index + 1, None);
+            }
+            self.dense[index].get_or_insert_with(|| Arc::new([W::ZERO; PAGE_SIZE]))
+        } else {
+            self.sparse
+                .entry(index)
+                .or_insert_with(|| Arc::new([W::ZERO; PAGE_SIZE]))
+        };
+        Arc::make_mut(page)
+    }
+
+    /// Iterate over all non-zero cells in address order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, W)> + '_ {
+        let dense = self
+            .dense
+            .iter()
+            .enumerate()
+            .filter_map(|(i, page)| Some((i, page.as_deref()?)));
+        let sparse = self.sparse.iter().map(|(&i, page)| (i, &**page));
+
+        dense.chain(sparse).flat_map(|(index, page)| {
+            page.iter()
+                .enumerate()
+                .filter(|(_, &value)| value != W::ZERO)
+                .map(move |(offset, &value)| ((index << PAGE_BITS) + offset, value))
+        })
+    }
+}
+
+impl<W: Word> Memory for PagedMemory<W> {
+    type Word = W;
+
+    fn get(&self, addr: usize) -> W {
+        self.page(addr >> PAGE_BITS)
+            .map_or(W::ZERO, |page| page[addr & (PAGE_SIZE - 1)])
+    }
+
+    fn put(&mut self, addr: usize, value: W) {
+        self.page_mut(addr >> PAGE_BITS)[addr & (PAGE_SIZE - 1)] = value;
+        self.size = self.size.max(addr + 1);
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<W: Word> PartialEq for PagedMemory<W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size && self.iter().eq(other.iter())
+    }
+}
+
+impl<W: Word> Eq for PagedMemory<W> {}
+
+impl<W: Word> fmt::Debug for PagedMemory<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<W: Word> FromIterator<W> for PagedMemory<W> {
+    fn from_iter<T: IntoIterator<Item = W>>(iter: T) -> Self {
+        let mut memory = Self::new();
+        for (addr, value) in iter.into_iter().enumerate() {
+            memory.put(addr, value);
+        }
+        memory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_put() {
+        let mut memory = PagedMemory::<isize>::new();
+        assert_eq!(memory.get(5), 0);
+        assert_eq!(memory.size(), 0);
+
+        memory.put(5, 10);
+        memory.put(PAGE_SIZE + 1, -3);
+        assert_eq!(memory.get(5), 10);
+        assert_eq!(memory.get(PAGE_SIZE + 1), -3);
+        assert_eq!(memory.size(), PAGE_SIZE + 2);
+        assert_eq!(
+            memory.iter().collect::<Vec<_>>(),
+            vec![(5, 10), (PAGE_SIZE + 1, -3)]
+        );
+    }
+
+    #[test]
+    fn test_sparse_high_addresses() {
+        let mut memory = PagedMemory::<isize>::new();
+        let addr = 1 << 40;
+        memory.put(addr, 7);
+        memory.put(3, 1);
+        assert_eq!(memory.get(addr), 7);
+        assert_eq!(memory.get(addr + 1), 0);
+        assert_eq!(memory.dense.len(), 1);
+        assert_eq!(memory.iter().collect::<Vec<_>>(), vec![(3, 1), (addr, 7)]);
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let original: PagedMemory = vec![1, 2, 3].into_iter().collect();
+        let mut fork = original.clone();
+        assert!(Arc::ptr_eq(
+            original.dense[0].as_ref().unwrap(),
+            fork.dense[0].as_ref().unwrap()
+        ));
+
+        fork.put(1, 20);
+        assert_eq!(original.get(1), 2);
+        assert_eq!(fork.get(1), 20);
+        assert!(!Arc::ptr_eq(
+            original.dense[0].as_ref().unwrap(),
+            fork.dense[0].as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_eq() {
+        let mut a: PagedMemory = vec![1, 0, 3].into_iter().collect();
+        let b: PagedMemory = vec![1, 0, 3].into_iter().collect();
+        assert_eq!(a, b);
+
+        a.put(1, 0);
+        assert_eq!(a, b);
+
+        a.put(2000, 0);
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,410 @@
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+use std::convert::TryInto;
+
+use super::{Interpreter, Memory, PagedMemory, PausedInterpreterInput, State};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
+    pub from: usize,
+    pub to: usize,
+    pub data: Vec<isize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Inject(Vec<Packet>),
+    Stop,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every machine has halted
+    Halted,
+    /// Nothing happened during a whole round even though idle input was available
+    Idle,
+    /// Every machine that is still running is blocked waiting for input
+    Deadlock,
+    /// The monitor asked the network to stop
+    Stopped,
+}
+
+pub trait Router {
+    /// Number of consecutive output values that make up a packet
+    fn packet_size(&self) -> usize {
+        1
+    }
+
+    fn route(&mut self, from: usize, data: Vec<isize>) -> Packet;
+}
+
+/// Routes packets where the first value is the destination address
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Addressed {
+    packet_size: usize,
+}
+
+impl Addressed {
+    pub fn new(packet_size: usize) -> Result<Self> {
+        if packet_size == 0 {
+            return Err(anyhow!("Packets need at least the destination address"));
+        }
+        Ok(Self { packet_size })
+    }
+}
+
+pub trait Monitor {
+    /// Called for every packet before it is delivered. Packets addressed outside
+    /// the network are dropped after this.
+    fn on_packet(&mut self, _packet: &Packet) -> Control {
+        Control::Continue
+    }
+
+    fn on_idle(&mut self) -> Control {
+        Control::Stop
+    }
+}
+
+impl Monitor for () {}
+
+impl<F: FnMut(usize, Vec<isize>) -> Packet> Router for F {
+    fn route(&mut self, from: usize, data: Vec<isize>) -> Packet {
+        self(from, data)
+    }
+}
+
+impl Router for Addressed {
+    fn packet_size(&self) -> usize {
+        self.packet_size
+    }
+
+    fn route(&mut self, from: usize, mut data: Vec<isize>) -> Packet {
+        let to = data
+            .drain(..data.len().min(1))
+            .next()
+            .and_then(|to| to.try_into().ok())
+            .unwrap_or(usize::MAX);
+        Packet { from, to, data }
+    }
+}
+
+enum NodeState<M: Memory> {
+    Ready(Interpreter<M>),
+    Waiting(PausedInterpreterInput<M>),
+    Halted,
+}
+
+struct Node<M: Memory> {
+    state: NodeState<M>,
+    queue: VecDeque<isize>,
+    output: Vec<isize>,
+}
+
+/// Deterministic round-robin scheduler for a set of connected machines
+pub struct Network<R, M: Memory = PagedMemory> {
+    nodes: Vec<Node<M>>,
+    router: R,
+    idle_input: Option<isize>,
+}
+
+impl<R: Router, M: Memory<Word = isize>> Network<R, M> {
+    pub fn new<T: IntoIterator<Item = Interpreter<M>>>(machines: T, router: R) -> Self {
+        Self {
+            nodes: machines
+                .into_iter()
+                .map(|computer| Node {
+                    state: NodeState::Ready(computer),
+                    queue: VecDeque::new(),
+                    output: Vec::new(),
+                })
+                .collect(),
+            router,
+            idle_input: None,
+        }
+    }
+
+    /// Value given to machines asking for input while their queue is empty. Without
+    /// one such machines block until a packet arrives.
+    pub fn with_idle_input(mut self, value: isize) -> Self {
+        self.idle_input = Some(value);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn is_halted(&self, node: usize) -> bool {
+        matches!(self.nodes[node].state, NodeState::Halted)
+    }
+
+    pub fn send(&mut self, to: usize, data: &[isize]) {
+        if let Some(node) = self.nodes.get_mut(to) {
+            node.queue.extend(data);
+        }
+    }
+
+    fn deliver(&mut self, packets: Vec<Packet>) {
+        for packet in packets {
+            self.send(packet.to, &packet.data);
+        }
+    }
+
+    /// Run a single machine until it halts or needs input that isn't available.
+    /// Returns whether the machine did anything besides polling for input, and
+    /// whether the monitor asked the network to stop.
+    fn run_node<T: Monitor>(&mut self, i: usize, monitor: &mut T) -> Result<(bool, bool)> {
+        let mut active = false;
+        let mut polled = false;
+        let mut state = match std::mem::replace(&mut self.nodes[i].state, NodeState::Halted) {
+            NodeState::Ready(computer) => {
+                active = true;
+                computer.run()?
+            }
+            NodeState::Waiting(c) => State::Input(c),
+            NodeState::Halted => return Ok((false, false)),
+        };
+
+        loop {
+            state = match state {
+                State::Input(c) => match self.nodes[i].queue.pop_front() {
+                    Some(value) => {
+                        active = true;
+                        c.resume(value)?
+                    }
+                    None => match self.idle_input {
+                        Some(value) if !polled => {
+                            polled = true;
+                            c.resume(value)?
+                        }
+                        _ => {
+                            self.nodes[i].state = NodeState::Waiting(c);
+                            return Ok((active, false));
+                        }
+                    },
+                },
+                State::Output(c) => {
+                    active = true;
+                    let node = &mut self.nodes[i];
+                    node.output.push(c.get());
+                    if node.output.len() == self.router.packet_size() {
+                        let data = std::mem::take(&mut node.output);
+                        let packet = self.router.route(i, data);
+                        match monitor.on_packet(&packet) {
+                            Control::Continue => {}
+                            Control::Inject(packets) => self.deliver(packets),
+                            Control::Stop => {
+                                self.send(packet.to, &packet.data);
+                                self.nodes[i].state = NodeState::Ready(c.into_interpreter());
+                                return Ok((active, true));
+                            }
+                        }
+                        self.send(packet.to, &packet.data);
+                    }
+                    c.resume()?
+                }
+                State::Limit(c) => return Err(anyhow!("Machine {}: {}", i, c.limit())),
+                State::Halt(_) => return Ok((active, false)),
+            };
+        }
+    }
+
+    pub fn run<T: Monitor>(&mut self, monitor: &mut T) -> Result<Outcome> {
+        loop {
+            let mut active = false;
+            for i in 0..self.nodes.len() {
+                let (node_active, stop) = self.run_node(i, monitor)?;
+                if stop {
+                    return Ok(Outcome::Stopped);
+                }
+                active |= node_active;
+            }
+
+            if self
+                .nodes
+                .iter()
+                .all(|node| matches!(node.state, NodeState::Halted))
+            {
+                return Ok(Outcome::Halted);
+            }
+
+            if active || self.nodes.iter().any(|node| !node.queue.is_empty()) {
+                continue;
+            }
+
+            match monitor.on_idle() {
+                Control::Inject(packets) if !packets.is_empty() => self.deliver(packets),
+                Control::Continue if self.idle_input.is_some() => {}
+                Control::Stop if self.idle_input.is_some() => return Ok(Outcome::Idle),
+                _ => return Ok(Outcome::Deadlock),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    // Adds its id to every received value and sends the result to the next node
+    fn relay(id: isize) -> Result<Interpreter> {
+        assemble(&format!(
+            "
+            loop: in x
+                  add x, #{}, x
+                  out x
+                  jnz #1, #loop
+            x:    data 0
+            ",
+            id
+        ))
+    }
+
+    fn sink() -> Result<Interpreter> {
+        assemble("loop: in 100\njnz #1, #loop")
+    }
+
+    // Sends (to, x) for each input pair (to, x) and ignores idle polls
+    fn forwarder() -> Result<Interpreter> {
+        assemble(
+            "
+            loop: in to
+                  eq to, #-1, idle
+                  jnz idle, #loop
+                  in x
+                  out to
+                  out x
+                  jnz #1, #loop
+            to:   data 0
+            x:    data 0
+            idle: data 0
+            ",
+        )
+    }
+
+    struct Recorder {
+        packets: Vec<Packet>,
+        idle: usize,
+    }
+
+    impl Monitor for Recorder {
+        fn on_packet(&mut self, packet: &Packet) -> Control {
+            self.packets.push(packet.clone());
+            if packet.data[0] >= 100 {
+                Control::Stop
+            } else {
+                Control::Continue
+            }
+        }
+
+        fn on_idle(&mut self) -> Control {
+            self.idle += 1;
+            if self.idle < 3 {
+                Control::Inject(vec![Packet {
+                    from: usize::MAX,
+                    to: 0,
+                    data: vec![1, 10 * self.idle as isize],
+                }])
+            } else {
+                Control::Stop
+            }
+        }
+    }
+
+    #[test]
+    fn test_ring() -> Result<()> {
+        let machines = (1..=3).map(relay).collect::<Result<Vec<_>>>()?;
+        let mut network = Network::new(machines, |from, data| Packet {
+            from,
+            to: (from + 1) % 3,
+            data,
+        });
+        network.send(0, &[0]);
+
+        let mut recorder = Recorder {
+            packets: Vec::new(),
+            idle: 0,
+        };
+        assert_eq!(network.run(&mut recorder)?, Outcome::Stopped);
+        let values: Vec<_> = recorder.packets.iter().map(|p| p.data[0]).collect();
+        assert_eq!(values[..4], [1, 3, 6, 7]);
+        assert_eq!(values.last(), Some(&102));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deadlock() -> Result<()> {
+        let machines = vec![relay(1)?, relay(2)?];
+        let mut network = Network::new(machines, |from, data| Packet { from, to: 5, data });
+        network.send(0, &[1]);
+        assert_eq!(network.run(&mut ())?, Outcome::Deadlock);
+        Ok(())
+    }
+
+    #[test]
+    fn test_idle_monitor() -> Result<()> {
+        let machines = vec![forwarder()?, sink()?];
+        let mut network = Network::new(machines, Addressed::new(2)?).with_idle_input(-1);
+        network.send(0, &[1, 5]);
+
+        let mut recorder = Recorder {
+            packets: Vec::new(),
+            idle: 0,
+        };
+        assert_eq!(network.run(&mut recorder)?, Outcome::Idle);
+        assert_eq!(
+            recorder.packets,
+            vec![
+                Packet {
+                    from: 0,
+                    to: 1,
+                    data: vec![5]
+                },
+                Packet {
+                    from: 0,
+                    to: 1,
+                    data: vec![10]
+                },
+                Packet {
+                    from: 0,
+                    to: 1,
+                    data: vec![20]
+                },
+            ]
+        );
+        assert_eq!(recorder.idle, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_addressed() -> Result<()> {
+        assert!(Addressed::new(0).is_err());
+        let mut router = Addressed::new(3)?;
+        assert_eq!(
+            router.route(2, vec![1, 7, 8]),
+            Packet {
+                from: 2,
+                to: 1,
+                data: vec![7, 8]
+            }
+        );
+        assert_eq!(router.route(2, vec![-1]).to, usize::MAX);
+        assert_eq!(router.route(2, vec![]).to, usize::MAX);
+        Ok(())
+    }
+
+    #[test]
+    fn test_halted() -> Result<()> {
+        let machines = vec![assemble("out #1\nhlt")?, assemble("in 0\nhlt")?];
+        let mut network = Network::new(machines, |from, data| Packet { from, to: 1, data });
+        assert_eq!(network.run(&mut ())?, Outcome::Halted);
+        assert!(network.is_halted(0) && network.is_halted(1));
+        Ok(())
+    }
+}
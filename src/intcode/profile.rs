@@ -0,0 +1,396 @@
+use anyhow::Result;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use super::{Interpreter, Memory, Opcode, Step};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Hits {
+    pub count: u64,
+    pub code: usize,
+    pub len: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Branch {
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockProfile {
+    pub start: usize,
+    pub end: usize,
+    pub entries: u64,
+    pub instructions: u64,
+}
+
+/// Execution statistics gathered while profiling is enabled.
+///
+/// Call stacks are inferred from the relative base: an `arb` that raises it is
+/// taken as the entry of a function and one that lowers it as the return.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub executed: u64,
+    pub instructions: BTreeMap<usize, Hits>,
+    pub opcodes: BTreeMap<usize, u64>,
+    pub branches: BTreeMap<usize, Branch>,
+    pub reads: BTreeMap<usize, u64>,
+    pub writes: BTreeMap<usize, u64>,
+    leaders: BTreeSet<usize>,
+    stack: Vec<usize>,
+    stacks: HashMap<Vec<usize>, u64>,
+    /// Outcome of the jump being executed, counted once the step completes
+    branch: Option<bool>,
+}
+
+impl Branch {
+    pub fn ratio(&self) -> f64 {
+        self.taken as f64 / (self.taken + self.not_taken).max(1) as f64
+    }
+}
+
+fn mnemonic(code: usize) -> String {
+    Opcode::new(code)
+        .info()
+        .map(|info| info.mnemonic.to_owned())
+        .unwrap_or_else(|| format!("op{}", code))
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    100.0 * part as f64 / total.max(1) as f64
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn record_branch(&mut self, taken: bool) {
+        self.branch = Some(taken);
+    }
+
+    pub(crate) fn record_step(&mut self, pc: usize, code: usize, next: usize, frame: Ordering) {
+        if self.executed == 0 {
+            self.leaders.insert(pc);
+        }
+        self.executed += 1;
+        *self.opcodes.entry(code).or_default() += 1;
+
+        let taken = self.branch.take();
+        let len = match code {
+            5 | 6 => {
+                let branch = self.branches.entry(pc).or_default();
+                match taken {
+                    Some(true) => branch.taken += 1,
+                    Some(false) => branch.not_taken += 1,
+                    None => {}
+                }
+                self.leaders.insert(next);
+                3
+            }
+            _ if next > pc => next - pc,
+            _ => {
+                self.leaders.insert(next);
+                1
+            }
+        };
+        let hits = self.instructions.entry(pc).or_default();
+        hits.count += 1;
+        hits.code = code;
+        hits.len = hits.len.max(len);
+
+        match self.stacks.get_mut(self.stack.as_slice()) {
+            Some(count) => *count += 1,
+            None => {
+                self.stacks.insert(self.stack.clone(), 1);
+            }
+        }
+        match frame {
+            Ordering::Greater => self.stack.push(pc),
+            Ordering::Less => {
+                self.stack.pop();
+            }
+            Ordering::Equal => {}
+        }
+    }
+
+    pub(crate) fn record_read(&mut self, addr: usize) {
+        *self.reads.entry(addr).or_default() += 1;
+    }
+
+    pub(crate) fn record_write(&mut self, addr: usize) {
+        *self.writes.entry(addr).or_default() += 1;
+    }
+
+    /// Basic blocks as observed during execution, hottest first
+    pub fn blocks(&self) -> Vec<BlockProfile> {
+        let mut blocks: Vec<BlockProfile> = Vec::new();
+        let mut expected = None;
+        for (&pc, hits) in &self.instructions {
+            match blocks.last_mut() {
+                Some(block) if expected == Some(pc) && !self.leaders.contains(&pc) => {
+                    block.end = pc + hits.len;
+                    block.instructions += hits.count;
+                }
+                _ => blocks.push(BlockProfile {
+                    start: pc,
+                    end: pc + hits.len,
+                    entries: hits.count,
+                    instructions: hits.count,
+                }),
+            }
+            expected = match hits.code {
+                5 | 6 | 99 => None,
+                _ => Some(pc + hits.len),
+            };
+        }
+        blocks.sort_by_key(|block| (std::cmp::Reverse(block.instructions), block.start));
+        blocks
+    }
+
+    /// Number of reads and writes per bucket of addresses, skipping unused
+    /// buckets. A bucket size of 0 is treated as 1.
+    pub fn heat_map(&self, bucket: usize) -> Vec<(usize, u64, u64)> {
+        let bucket = bucket.max(1);
+        let mut buckets: BTreeMap<usize, (u64, u64)> = BTreeMap::new();
+        for (&addr, &count) in &self.reads {
+            buckets.entry(addr / bucket * bucket).or_default().0 += count;
+        }
+        for (&addr, &count) in &self.writes {
+            buckets.entry(addr / bucket * bucket).or_default().1 += count;
+        }
+        buckets
+            .into_iter()
+            .map(|(start, (reads, writes))| (start, reads, writes))
+            .collect()
+    }
+
+    /// Stacks in the folded format read by flame graph tools, one
+    /// `frame;frame count` line per stack
+    pub fn folded(&self) -> String {
+        let mut lines: Vec<_> = self
+            .stacks
+            .iter()
+            .map(|(stack, count)| {
+                let frames: Vec<_> = std::iter::once("main".to_owned())
+                    .chain(stack.iter().map(|pc| format!("fn_{:04}", pc)))
+                    .collect();
+                format!("{} {}\n", frames.join(";"), count)
+            })
+            .collect();
+        lines.sort();
+        lines.concat()
+    }
+
+    pub fn report(&self, limit: usize) -> String {
+        let mut output = format!("Instructions executed: {}\n", self.executed);
+
+        output.push_str("\nHottest blocks:\n   start    end     entries  instructions       %\n");
+        for block in self.blocks().iter().take(limit) {
+            output.push_str(&format!(
+                "    {:04}   {:04}  {:>10}    {:>10}  {:>5.1}%\n",
+                block.start,
+                block.end,
+                block.entries,
+                block.instructions,
+                percent(block.instructions, self.executed),
+            ));
+        }
+
+        let mut opcodes: Vec<_> = self.opcodes.iter().collect();
+        opcodes.sort_by_key(|&(&code, &count)| (std::cmp::Reverse(count), code));
+        output.push_str("\nOpcodes:\n");
+        for (&code, &count) in opcodes {
+            output.push_str(&format!(
+                "    {:<6}{:>12}  {:>5.1}%\n",
+                mnemonic(code),
+                count,
+                percent(count, self.executed),
+            ));
+        }
+
+        if !self.branches.is_empty() {
+            output.push_str("\nBranches:\n");
+            for (pc, branch) in &self.branches {
+                output.push_str(&format!(
+                    "    {:04}  {:<4} taken {:>10}  not taken {:>10}  {:>5.1}%\n",
+                    pc,
+                    mnemonic(self.instructions[pc].code),
+                    branch.taken,
+                    branch.not_taken,
+                    100.0 * branch.ratio(),
+                ));
+            }
+        }
+
+        let heat_map = self.heat_map(64);
+        if !heat_map.is_empty() {
+            output.push_str("\nMemory heat map:\n");
+            for (start, reads, writes) in heat_map {
+                output.push_str(&format!(
+                    "    {:04}-{:04}  reads {:>10}  writes {:>10}\n",
+                    start,
+                    start + 63,
+                    reads,
+                    writes,
+                ));
+            }
+        }
+        output
+    }
+}
+
+/// Run a program to completion with profiling enabled, returning its output
+pub fn profile<M, T>(mut computer: Interpreter<M>, input: T) -> Result<(Vec<M::Word>, Profile)>
+where
+    M: Memory,
+    T: IntoIterator<Item = M::Word>,
+{
+    let mut input = input.into_iter();
+    let mut output = Vec::new();
+    computer.enable_profiling();
+    loop {
+        match computer.step_with(&mut input)? {
+            Step::Output(value) => output.push(value),
+            Step::Halt => break,
+            _ => {}
+        }
+    }
+    Ok((output, computer.disable_profiling().unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    const COUNTDOWN: &str = "
+              in n
+        loop: add n, #-1, n
+              jnz n, #loop
+              hlt
+        n:    data 0
+    ";
+
+    #[test]
+    fn test_counts() -> Result<()> {
+        let (_, profile) = profile(assemble(COUNTDOWN)?, vec![3])?;
+        assert_eq!(profile.executed, 8);
+        assert_eq!(profile.instructions[&2].count, 3);
+        assert_eq!(profile.opcodes[&1], 3);
+        assert_eq!(profile.opcodes[&5], 3);
+        assert_eq!(
+            profile.branches[&6],
+            Branch {
+                taken: 2,
+                not_taken: 1
+            }
+        );
+        assert_eq!(profile.reads[&10], 6);
+        assert_eq!(profile.writes[&10], 4);
+        assert_eq!(profile.heat_map(8), vec![(8, 6, 4)]);
+        assert_eq!(profile.heat_map(0), profile.heat_map(1));
+        assert_eq!(profile.heat_map(0), vec![(10, 6, 4)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_jump_to_next() -> Result<()> {
+        // Both jumps end up at the following instruction
+        let (_, profile) = profile(
+            assemble(
+                "jnz #1, #3
+jz #1, #6
+hlt",
+            )?,
+            vec![],
+        )?;
+        assert_eq!(
+            profile.branches[&0],
+            Branch {
+                taken: 1,
+                not_taken: 0
+            }
+        );
+        assert_eq!(
+            profile.branches[&3],
+            Branch {
+                taken: 0,
+                not_taken: 1
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rolled_back_jump() -> Result<()> {
+        // The jump reads its condition above the address limit
+        let mut computer = Interpreter::from_iter(vec![1005, 5000, 0]);
+        computer.enable_profiling();
+        computer.set_limits(crate::intcode::Limits::new().max_address(1000));
+        let c = computer.run()?.expect_limit();
+        let profile = c.interpreter().profile().unwrap();
+        assert!(profile.branches.is_empty());
+        assert!(profile.report(10).starts_with("Instructions executed: 0"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_blocks() -> Result<()> {
+        let (_, profile) = profile(assemble(COUNTDOWN)?, vec![3])?;
+        assert_eq!(
+            profile.blocks(),
+            vec![
+                BlockProfile {
+                    start: 2,
+                    end: 9,
+                    entries: 3,
+                    instructions: 6
+                },
+                BlockProfile {
+                    start: 0,
+                    end: 2,
+                    entries: 1,
+                    instructions: 1
+                },
+                BlockProfile {
+                    start: 9,
+                    end: 10,
+                    entries: 1,
+                    instructions: 1
+                },
+            ]
+        );
+        assert!(profile.report(10).contains("    0002   0009"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_folded() -> Result<()> {
+        let computer = assemble(
+            "
+                  jnz #1, #f
+            back: hlt
+            f:    arb #2
+                  arb #-2
+                  jz #0, #back
+            ",
+        )?;
+        let (_, profile) = profile(computer, vec![])?;
+        assert_eq!(profile.folded(), "main 4\nmain;fn_0004 1\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_boost() -> Result<()> {
+        let (output, profile) = profile(Interpreter::from_path("data/day9.txt")?, vec![1])?;
+        assert_eq!(output, vec![3100786347]);
+        assert_eq!(profile.executed, profile.opcodes.values().sum::<u64>());
+        assert_eq!(
+            profile.executed,
+            profile.blocks().iter().map(|b| b.instructions).sum::<u64>()
+        );
+        Ok(())
+    }
+}
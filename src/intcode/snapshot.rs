@@ -0,0 +1,424 @@
+use anyhow::{anyhow, Result};
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+use super::{Interpreter, Memory, PagedMemory, PausedInterpreterInput, PausedInterpreterOutput};
+
+const VERSION: u64 = 1;
+const TEXT_HEADER: &str = "intcode-snapshot";
+const BINARY_MAGIC: &[u8] = b"ICS";
+
+/// Machine state that can be saved to and restored from disk. Limits, traces,
+/// extension opcodes and the arithmetic and engine settings are not part of a
+/// snapshot, so restored machines use the defaults.
+#[derive(Clone, Debug)]
+pub enum Snapshot {
+    Interpreter(Interpreter),
+    Input(PausedInterpreterInput),
+    Output(PausedInterpreterOutput),
+}
+
+struct Fields {
+    pc: usize,
+    rel_base: isize,
+    extra: isize,
+    size: usize,
+    runs: Vec<(usize, Vec<isize>)>,
+}
+
+fn runs(memory: &PagedMemory) -> Vec<(usize, Vec<isize>)> {
+    let mut runs: Vec<(usize, Vec<isize>)> = Vec::new();
+    for (addr, value) in memory.iter() {
+        match runs.last_mut() {
+            Some((start, values)) if *start + values.len() == addr => values.push(value),
+            _ => runs.push((addr, vec![value])),
+        }
+    }
+    runs
+}
+
+impl Snapshot {
+    fn kind(&self) -> (&'static str, u8) {
+        match self {
+            Snapshot::Interpreter(_) => ("interpreter", 0),
+            Snapshot::Input(_) => ("input", 1),
+            Snapshot::Output(_) => ("output", 2),
+        }
+    }
+
+    fn fields(&self) -> Fields {
+        let (computer, extra) = match self {
+            Snapshot::Interpreter(computer) => (computer, 0),
+            Snapshot::Input(c) => (&c.inner, c.pos as isize),
+            Snapshot::Output(c) => (&c.inner, c.value),
+        };
+        Fields {
+            pc: computer.pc,
+            rel_base: computer.rel_base,
+            extra,
+            size: computer.memory.size(),
+            runs: runs(&computer.memory),
+        }
+    }
+
+    fn from_fields(kind: u8, fields: Fields) -> Result<Self> {
+        let mut memory = PagedMemory::new();
+        if fields.size > 0 {
+            memory.put(fields.size - 1, 0);
+        }
+        for (start, values) in fields.runs {
+            match start.checked_add(values.len()) {
+                Some(end) if end <= fields.size => {}
+                _ => return Err(anyhow!("Memory at {} extends past the memory size", start)),
+            }
+            for (i, value) in values.into_iter().enumerate() {
+                memory.put(start + i, value);
+            }
+        }
+
+        let mut computer = Interpreter::new(memory);
+        computer.pc = fields.pc;
+        computer.op_pc = fields.pc;
+        computer.rel_base = fields.rel_base;
+
+        Ok(match kind {
+            0 => Snapshot::Interpreter(computer),
+            1 => Snapshot::Input(PausedInterpreterInput {
+                inner: computer,
+                pos: fields
+                    .extra
+                    .try_into()
+                    .map_err(|_| anyhow!("Input position must not be negative"))?,
+            }),
+            2 => Snapshot::Output(PausedInterpreterOutput {
+                inner: computer,
+                value: fields.extra,
+            }),
+            kind => return Err(anyhow!("Unknown snapshot kind {}", kind)),
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let (kind, _) = self.kind();
+        let fields = self.fields();
+        let mut output = format!(
+            "{} {}\nkind {}\npc {}\nrel_base {}\n",
+            TEXT_HEADER, VERSION, kind, fields.pc, fields.rel_base
+        );
+        match self {
+            Snapshot::Interpreter(_) => {}
+            Snapshot::Input(_) => output.push_str(&format!("pos {}\n", fields.extra)),
+            Snapshot::Output(_) => output.push_str(&format!("value {}\n", fields.extra)),
+        }
+        output.push_str(&format!("size {}\nmemory\n", fields.size));
+        for (start, values) in fields.runs {
+            let values: Vec<_> = values.iter().map(|v| v.to_string()).collect();
+            output.push_str(&format!("{} {}\n", start, values.join(",")));
+        }
+        output
+    }
+
+    pub fn from_text(text: &str) -> Result<Self> {
+        let mut lines = text.lines().enumerate();
+        let mut field = |name: &str| -> Result<String> {
+            let (i, line) = lines
+                .next()
+                .ok_or_else(|| anyhow!("Unexpected end of snapshot, expected {}", name))?;
+            let mut parts = line.splitn(2, ' ');
+            if parts.next() != Some(name) {
+                return Err(anyhow!("Line {}: expected {}", i + 1, name));
+            }
+            Ok(parts.next().unwrap_or("").to_owned())
+        };
+
+        let version: u64 = field(TEXT_HEADER)?.parse()?;
+        if version != VERSION {
+            return Err(anyhow!("Unsupported snapshot version {}", version));
+        }
+        let kind = match field("kind")?.as_str() {
+            "interpreter" => 0,
+            "input" => 1,
+            "output" => 2,
+            kind => return Err(anyhow!("Unknown snapshot kind {:?}", kind)),
+        };
+        let pc = field("pc")?.parse()?;
+        let rel_base = field("rel_base")?.parse()?;
+        let extra = match kind {
+            1 => field("pos")?.parse()?,
+            2 => field("value")?.parse()?,
+            _ => 0,
+        };
+        let size = field("size")?.parse()?;
+        field("memory")?;
+
+        let runs = lines
+            .map(|(i, line)| -> Result<(usize, Vec<isize>)> {
+                let mut parts = line.splitn(2, ' ');
+                let start = parts.next().unwrap_or("").parse()?;
+                let values = parts
+                    .next()
+                    .ok_or_else(|| anyhow!("Line {}: expected memory values", i + 1))?
+                    .split(',')
+                    .map(|value| value.parse())
+                    .collect::<Result<_, _>>()?;
+                Ok((start, values))
+            })
+            .collect::<Result<_>>()?;
+
+        Self::from_fields(
+            kind,
+            Fields {
+                pc,
+                rel_base,
+                extra,
+                size,
+                runs,
+            },
+        )
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (_, kind) = self.kind();
+        let fields = self.fields();
+        let mut output = BINARY_MAGIC.to_vec();
+        write_varint(&mut output, VERSION);
+        output.push(kind);
+        write_varint(&mut output, fields.pc as u64);
+        write_varint(&mut output, zigzag(fields.rel_base));
+        write_varint(&mut output, zigzag(fields.extra));
+        write_varint(&mut output, fields.size as u64);
+        write_varint(&mut output, fields.runs.len() as u64);
+        for (start, values) in fields.runs {
+            write_varint(&mut output, start as u64);
+            write_varint(&mut output, values.len() as u64);
+            for value in values {
+                write_varint(&mut output, zigzag(value));
+            }
+        }
+        output
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut input = bytes
+            .strip_prefix(BINARY_MAGIC)
+            .ok_or_else(|| anyhow!("Not a binary Intcode snapshot"))?;
+
+        let version = read_varint(&mut input)?;
+        if version != VERSION {
+            return Err(anyhow!("Unsupported snapshot version {}", version));
+        }
+        let (&kind, rest) = input
+            .split_first()
+            .ok_or_else(|| anyhow!("Unexpected end of snapshot"))?;
+        input = rest;
+
+        let pc = read_address(&mut input)?;
+        let rel_base = unzigzag(read_varint(&mut input)?);
+        let extra = unzigzag(read_varint(&mut input)?);
+        let size = read_address(&mut input)?;
+        let mut runs = Vec::new();
+        for _ in 0..read_varint(&mut input)? {
+            let start = read_address(&mut input)?;
+            let values = (0..read_varint(&mut input)?)
+                .map(|_| Ok(unzigzag(read_varint(&mut input)?)))
+                .collect::<Result<_>>()?;
+            runs.push((start, values));
+        }
+        if !input.is_empty() {
+            return Err(anyhow!("Trailing data after snapshot"));
+        }
+
+        Self::from_fields(
+            kind,
+            Fields {
+                pc,
+                rel_base,
+                extra,
+                size,
+                runs,
+            },
+        )
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, binary: bool) -> Result<()> {
+        if binary {
+            fs::write(path, self.to_bytes())?;
+        } else {
+            fs::write(path, self.to_text())?;
+        }
+        Ok(())
+    }
+
+    /// Load a snapshot in either format
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let bytes = fs::read(path)?;
+        if bytes.starts_with(BINARY_MAGIC) {
+            Self::from_bytes(&bytes)
+        } else {
+            Self::from_text(std::str::from_utf8(&bytes)?)
+        }
+    }
+}
+
+fn zigzag(value: isize) -> u64 {
+    ((value << 1) ^ (value >> (isize::BITS - 1))) as u64
+}
+
+fn unzigzag(value: u64) -> isize {
+    ((value >> 1) as isize) ^ -((value & 1) as isize)
+}
+
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input
+            .split_first()
+            .ok_or_else(|| anyhow!("Unexpected end of snapshot"))?;
+        *input = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("Variable length integer is too long"))
+}
+
+fn read_address(input: &mut &[u8]) -> Result<usize> {
+    let value = read_varint(input)?;
+    value
+        .try_into()
+        .map_err(|_| anyhow!("Address {} is out of range", value))
+}
+
+impl From<Interpreter> for Snapshot {
+    fn from(computer: Interpreter) -> Self {
+        Snapshot::Interpreter(computer)
+    }
+}
+
+impl From<PausedInterpreterInput> for Snapshot {
+    fn from(c: PausedInterpreterInput) -> Self {
+        Snapshot::Input(c)
+    }
+}
+
+impl From<PausedInterpreterOutput> for Snapshot {
+    fn from(c: PausedInterpreterOutput) -> Self {
+        Snapshot::Output(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(snapshot: Snapshot) -> Result<()> {
+        let text = snapshot.to_text();
+        assert_eq!(Snapshot::from_text(&text)?.to_text(), text);
+        let bytes = snapshot.to_bytes();
+        assert_eq!(Snapshot::from_bytes(&bytes)?.to_bytes(), bytes);
+        assert_eq!(Snapshot::from_bytes(&bytes)?.to_text(), text);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zigzag() {
+        for &value in &[0, 1, -1, 63, -64, isize::MAX, isize::MIN] {
+            assert_eq!(unzigzag(zigzag(value)), value);
+        }
+    }
+
+    #[test]
+    fn test_text_format() -> Result<()> {
+        let mut computer = Interpreter::from_iter(vec![3, 0, 0, 5, 6]);
+        computer.put(1000, -2);
+        let c = computer.run()?.expect_input();
+        assert_eq!(
+            Snapshot::from(c).to_text(),
+            [
+                "intcode-snapshot 1",
+                "kind input",
+                "pc 2",
+                "rel_base 0",
+                "pos 0",
+                "size 1001",
+                "memory",
+                "0 3",
+                "3 5,6",
+                "1000 -2",
+                "",
+            ]
+            .join("\n")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let computer = Interpreter::from_path("data/day9.txt")?;
+        round_trip(computer.clone().into())?;
+
+        round_trip(Snapshot::Input(computer.run()?.expect_input()))?;
+
+        let c = Interpreter::from_iter(vec![109, -3, 104, -7, 99])
+            .run()?
+            .expect_output();
+        round_trip(Snapshot::Output(c))
+    }
+
+    #[test]
+    fn test_resume_restored() -> Result<()> {
+        let c = Interpreter::from_path("data/day9.txt")?
+            .run()?
+            .expect_input();
+
+        let bytes = Snapshot::from(c).to_bytes();
+        let restored = match Snapshot::from_bytes(&bytes)? {
+            Snapshot::Input(c) => c,
+            snapshot => panic!("Expected input snapshot, got {:?}", snapshot),
+        };
+        assert_eq!(restored.resume(1)?.expect_output().get(), 3100786347);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Snapshot::from_text("intcode-snapshot 2\n").is_err());
+        assert!(Snapshot::from_text("kind input\n").is_err());
+        assert!(Snapshot::from_bytes(b"ICS\x02").is_err());
+        assert!(Snapshot::from_bytes(b"ICS\x01\x00\x00").is_err());
+        assert!(Snapshot::from_bytes(b"XYZ").is_err());
+
+        let header = "intcode-snapshot 1\nkind interpreter\npc 0\nrel_base 0\n";
+        let err = |memory: &str| {
+            Snapshot::from_text(&format!("{}{}", header, memory))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err("size 2\nmemory\n1 5,6"),
+            "Memory at 1 extends past the memory size"
+        );
+        assert_eq!(
+            err(&format!(
+                "size {}\nmemory\n{} 1,2",
+                usize::MAX,
+                usize::MAX - 1
+            )),
+            format!("Memory at {} extends past the memory size", usize::MAX - 1)
+        );
+        assert_eq!(
+            err(&format!("size 1\nmemory\n{} 1", usize::MAX)),
+            format!("Memory at {} extends past the memory size", usize::MAX)
+        );
+    }
+}
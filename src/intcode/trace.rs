@@ -0,0 +1,177 @@
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+use super::{Interpreter, Mode, Step, Word};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryWrite<W = isize> {
+    pub addr: usize,
+    pub old: W,
+    pub new: W,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceRecord<W = isize> {
+    pub pc: usize,
+    pub opcode: W,
+    pub modes: Vec<Mode>,
+    pub operands: Vec<W>,
+    pub write: Option<MemoryWrite<W>>,
+    pub rel_base: Option<(W, W)>,
+}
+
+pub struct Trace<I> {
+    computer: Interpreter,
+    input: I,
+    records: VecDeque<TraceRecord>,
+    done: bool,
+}
+
+impl<W: Word> TraceRecord<W> {
+    pub fn new(pc: usize, opcode: W) -> Self {
+        Self {
+            pc,
+            opcode,
+            modes: Vec::new(),
+            operands: Vec::new(),
+            write: None,
+            rel_base: None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let join = |items: Vec<String>| items.join(",");
+        let modes = join(
+            self.modes
+                .iter()
+                .map(|mode| match mode {
+                    Mode::Pos => "\"position\"".to_owned(),
+                    Mode::Immediate => "\"immediate\"".to_owned(),
+                    Mode::Relative => "\"relative\"".to_owned(),
+                })
+                .collect(),
+        );
+        let operands = join(self.operands.iter().map(|x| x.to_string()).collect());
+        let write = match self.write {
+            Some(MemoryWrite { addr, old, new }) => {
+                format!("{{\"addr\":{},\"old\":{},\"new\":{}}}", addr, old, new)
+            }
+            None => "null".to_owned(),
+        };
+        let rel_base = match self.rel_base {
+            Some((old, new)) => format!("{{\"old\":{},\"new\":{}}}", old, new),
+            None => "null".to_owned(),
+        };
+
+        format!(
+            "{{\"pc\":{},\"opcode\":{},\"modes\":[{}],\"operands\":[{}],\"write\":{},\"rel_base\":{}}}",
+            self.pc, self.opcode, modes, operands, write, rel_base
+        )
+    }
+}
+
+pub fn write_json_lines<'a, R, T, W>(records: R, mut output: T) -> io::Result<()>
+where
+    R: IntoIterator<Item = &'a TraceRecord<W>>,
+    T: Write,
+    W: Word,
+{
+    for record in records {
+        writeln!(output, "{}", record.to_json())?;
+    }
+    Ok(())
+}
+
+impl<I: Iterator<Item = isize>> Trace<I> {
+    pub fn new<T: IntoIterator<IntoIter = I>>(mut computer: Interpreter, input: T) -> Self {
+        computer.enable_tracing();
+        Self {
+            computer,
+            input: input.into_iter(),
+            records: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    fn advance(&mut self) -> Result<()> {
+        if let Step::Halt = self.computer.step_with(&mut self.input)? {
+            self.done = true;
+        }
+        self.records.extend(self.computer.take_trace());
+        Ok(())
+    }
+}
+
+impl<I: Iterator<Item = isize>> Iterator for Trace<I> {
+    type Item = Result<TraceRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.records.is_empty() && !self.done {
+            if let Err(e) = self.advance() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        self.records.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    #[test]
+    fn test_trace() -> Result<()> {
+        let computer = assemble("in 20\nmul 20, #3, @21\narb #4\nout 21\nhlt")?;
+        let records = Trace::new(computer, vec![5]).collect::<Result<Vec<_>>>()?;
+        assert_eq!(records.len(), 5);
+        assert_eq!(
+            records[0].write,
+            Some(MemoryWrite {
+                addr: 20,
+                old: 0,
+                new: 5
+            })
+        );
+        assert_eq!(records[1].pc, 2);
+        assert_eq!(records[1].opcode, 21002);
+        assert_eq!(
+            records[1].modes,
+            vec![Mode::Pos, Mode::Immediate, Mode::Relative]
+        );
+        assert_eq!(records[1].operands, vec![5, 3, 21]);
+        assert_eq!(records[2].rel_base, Some((0, 4)));
+        assert_eq!(records[3].operands, vec![15]);
+        assert_eq!(records[4].opcode, 99);
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_input() -> Result<()> {
+        let mut trace = Trace::new(assemble("in 0\nhlt")?, vec![]);
+        assert!(trace.next().unwrap().is_err());
+        assert!(trace.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_lines() -> Result<()> {
+        let computer = assemble("add #2, 0, 0\narb #-1\nhlt")?;
+        let records = Trace::new(computer, vec![]).collect::<Result<Vec<_>>>()?;
+        let mut output = Vec::new();
+        write_json_lines(&records, &mut output)?;
+        assert_eq!(
+            String::from_utf8(output)?,
+            [
+                r#"{"pc":0,"opcode":101,"modes":["immediate","position","position"],"operands":[2,101,0],"write":{"addr":0,"old":101,"new":103},"rel_base":null}"#,
+                r#"{"pc":4,"opcode":109,"modes":["immediate"],"operands":[-1],"write":null,"rel_base":{"old":0,"new":-1}}"#,
+                r#"{"pc":6,"opcode":99,"modes":[],"operands":[],"write":null,"rel_base":null}"#,
+                "",
+            ]
+            .join("\n")
+        );
+        Ok(())
+    }
+}
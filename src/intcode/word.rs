@@ -0,0 +1,65 @@
+use std::fmt;
+use std::hash::Hash;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+/// Integer type stored in each memory cell
+pub trait Word:
+    Copy
+    + Default
+    + Ord
+    + Hash
+    + fmt::Debug
+    + fmt::Display
+    + FromStr<Err = ParseIntError>
+    + Send
+    + Sync
+    + 'static
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn to_isize(self) -> Option<isize>;
+    fn to_i128(self) -> i128;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn wrapping_add(self, other: Self) -> Self;
+    fn wrapping_mul(self, other: Self) -> Self;
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn to_isize(self) -> Option<isize> {
+                    std::convert::TryInto::try_into(self).ok()
+                }
+
+                fn to_i128(self) -> i128 {
+                    self as i128
+                }
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+
+                fn checked_mul(self, other: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, other)
+                }
+
+                fn wrapping_add(self, other: Self) -> Self {
+                    <$t>::wrapping_add(self, other)
+                }
+
+                fn wrapping_mul(self, other: Self) -> Self {
+                    <$t>::wrapping_mul(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_word!(i32, i64, i128, isize);
@@ -1,5 +1,14 @@
 use anyhow::{anyhow, Result};
-use aoc_2019::{day1, day2, day3, day4, day5, day6, day8, day9};
+use aoc_2019::intcode::analysis::analyze;
+use aoc_2019::intcode::ascii::{self, AsciiConsole};
+use aoc_2019::intcode::debugger::{self, Debugger};
+use aoc_2019::intcode::profile::profile;
+use aoc_2019::intcode::trace::{self, Trace};
+use aoc_2019::intcode::{disasm, Interpreter};
+use aoc_2019::{
+    day1, day10, day11, day12, day13, day14, day15, day16, day2, day3, day4, day5, day6, day7,
+    day8, day9,
+};
 
 fn pad_newlines(answer: String) -> String {
     answer.lines().collect::<Vec<_>>().join("\n   ")
@@ -12,6 +21,90 @@ fn as_result<A: ToString, B: ToString>(value: (A, Option<B>)) -> (String, Option
     )
 }
 
+fn disassemble(args: &[String]) -> Result<()> {
+    if args.len() != 1 {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    print!("{}", disasm::listing(&Interpreter::from_path(&args[0])?));
+    Ok(())
+}
+
+fn analyze_program(args: &[String]) -> Result<()> {
+    let analysis = match args {
+        [path] => analyze(&Interpreter::from_path(path)?),
+        [path, format] if format == "dot" => {
+            print!("{}", analyze(&Interpreter::from_path(path)?).to_dot());
+            return Ok(());
+        }
+        _ => return Err(anyhow!("Expected path to input and optionally dot")),
+    };
+    print!("{}", analysis.summary());
+    Ok(())
+}
+
+fn debug(args: &[String]) -> Result<()> {
+    if args.is_empty() {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    let mut debugger = Debugger::new(Interpreter::from_path(&args[0])?);
+    let mut inputs = &args[1..];
+    if inputs.first().map(String::as_str) == Some("history") {
+        debugger = debugger.with_history();
+        inputs = &inputs[1..];
+    }
+    for value in inputs {
+        debugger.feed(value.parse()?);
+    }
+
+    let stdin = std::io::stdin();
+    debugger::repl(&mut debugger, stdin.lock(), std::io::stdout())
+}
+
+fn run_ascii(args: &[String]) -> Result<()> {
+    if args.len() != 1 {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    let mut console = AsciiConsole::new(Interpreter::from_path(&args[0])?)?;
+    let stdin = std::io::stdin();
+    ascii::interactive(&mut console, stdin.lock(), std::io::stdout())
+}
+
+fn write_trace(args: &[String]) -> Result<()> {
+    if args.is_empty() {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    let input = args[1..]
+        .iter()
+        .map(|value| value.parse())
+        .collect::<Result<Vec<isize>, _>>()?;
+    let records =
+        Trace::new(Interpreter::from_path(&args[0])?, input).collect::<Result<Vec<_>>>()?;
+    trace::write_json_lines(&records, std::io::stdout().lock())?;
+    Ok(())
+}
+
+fn write_profile(args: &[String], folded: bool) -> Result<()> {
+    if args.is_empty() {
+        return Err(anyhow!("Expected path to input"));
+    }
+
+    let input = args[1..]
+        .iter()
+        .map(|value| value.parse())
+        .collect::<Result<Vec<isize>, _>>()?;
+    let (_, profile) = profile(Interpreter::from_path(&args[0])?, input)?;
+    if folded {
+        print!("{}", profile.folded());
+    } else {
+        print!("{}", profile.report(20));
+    }
+    Ok(())
+}
+
 fn main() -> Result<()> {
     let args: Vec<_> = std::env::args().collect();
 
@@ -19,17 +112,40 @@ fn main() -> Result<()> {
         return Err(anyhow!("Not enough arguments"));
     }
 
-    let result = match args[1].parse()? {
+    match args[1].as_str() {
+        "disasm" => return disassemble(&args[2..]),
+        "analyze" => return analyze_program(&args[2..]),
+        "debug" => return debug(&args[2..]),
+        "run-ascii" => return run_ascii(&args[2..]),
+        "trace" => return write_trace(&args[2..]),
+        "profile" => return write_profile(&args[2..], false),
+        "flamegraph" => return write_profile(&args[2..], true),
+        _ => {}
+    }
+
+    let day = args[1].parse()?;
+    if !(1..=25).contains(&day) {
+        return Err(anyhow!("Day {} is not a valid day for advent of code", day));
+    }
+
+    let result = match day {
         1 => as_result(day1::main(&args[2..])?),
         2 => as_result(day2::main(&args[2..])?),
         3 => as_result(day3::main(&args[2..])?),
         4 => as_result(day4::main(&args[2..])?),
         5 => as_result(day5::main(&args[2..])?),
         6 => as_result(day6::main(&args[2..])?),
+        7 => as_result(day7::main(&args[2..])?),
         8 => as_result(day8::main(&args[2..])?),
         9 => as_result(day9::main(&args[2..])?),
-        1..=25 => return Err(anyhow!("No implementation for this day yet")),
-        day => return Err(anyhow!("Day {} is not a valid day for advent of code", day)),
+        10 => as_result(day10::main(&args[2..])?),
+        11 => as_result(day11::main(&args[2..])?),
+        12 => as_result(day12::main(&args[2..])?),
+        13 => as_result(day13::main(&args[2..])?),
+        14 => as_result(day14::main(&args[2..])?),
+        15 => as_result(day15::main(&args[2..])?),
+        16 => as_result(day16::main(&args[2..])?),
+        _ => return Err(anyhow!("No implementation for this day yet")),
     };
 
     println!("A: {}", pad_newlines(result.0));
@@ -0,0 +1,82 @@
+/// Greatest common divisor, always non-negative
+pub fn gcd(a: isize, b: isize) -> isize {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let rem = a % b;
+        a = b;
+        b = rem;
+    }
+    a
+}
+
+/// Least common multiple, zero if either argument is zero
+pub fn lcm(a: usize, b: usize) -> usize {
+    match gcd(a as isize, b as isize) {
+        0 => 0,
+        n => a / n as usize * b,
+    }
+}
+
+/// Find where the sequence `start, step(start), step(step(start)), ...`
+/// starts repeating using Brent's algorithm. Returns the index of the first
+/// value in the cycle and the length of the cycle.
+pub fn find_cycle<T, F>(start: T, mut step: F) -> (usize, usize)
+where
+    T: Clone + PartialEq,
+    F: FnMut(&T) -> T,
+{
+    let mut power = 1;
+    let mut len = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(&start);
+    while tortoise != hare {
+        if power == len {
+            tortoise = hare.clone();
+            power *= 2;
+            len = 0;
+        }
+        hare = step(&hare);
+        len += 1;
+    }
+
+    let mut tortoise = start.clone();
+    let mut hare = start;
+    for _ in 0..len {
+        hare = step(&hare);
+    }
+    let mut first = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        first += 1;
+    }
+    (first, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(-4, 6), 2);
+        assert_eq!(gcd(0, -5), 5);
+        assert_eq!(gcd(0, 0), 0);
+    }
+
+    #[test]
+    fn test_lcm() {
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(7, 1), 7);
+        assert_eq!(lcm(0, 3), 0);
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        assert_eq!(find_cycle(0, |&x| (x + 1) % 5), (0, 5));
+        assert_eq!(find_cycle(1, |&x| x), (0, 1));
+        // 2, 4, 8, 16, 10, 20, 18, 14, 6, 12, 2 (mod 22) after the tail 1
+        assert_eq!(find_cycle(1, |&x| x * 2 % 22), (1, 10));
+    }
+}
@@ -1,5 +1,11 @@
 use anyhow::Result;
-use aoc_2019::{day1, day2, day3, day4, day5, day6, day8, day9};
+use aoc_2019::{
+    day1, day10, day11, day12, day13, day14, day15, day16, day2, day3, day4, day5, day6, day7,
+    day8, day9,
+};
+
+// Days 7, 11, 13 and 15 use made up inputs, small programs written with the
+// assembler, and days 10, 12, 14 and 16 use examples from the puzzles
 
 #[test]
 fn test_day1() {
@@ -50,6 +56,15 @@ fn test_day6() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_day7() -> Result<()> {
+    assert_eq!(
+        day7::main(&["data/day7.txt".to_owned()])?,
+        (426, Some(267421))
+    );
+    Ok(())
+}
+
 #[test]
 fn test_day8() -> Result<()> {
     assert_eq!(
@@ -57,7 +72,7 @@ fn test_day8() -> Result<()> {
         (
             2176,
             Some(
-                vec![
+                [
                     " ##  #   ##  # ###  #   #",
                     "#  # #   ## #  #  # #   #",
                     "#     # # ##   ###   # # ",
@@ -79,3 +94,63 @@ fn test_day9() {
         (3100786347, Some(87023))
     );
 }
+
+#[test]
+fn test_day10() -> Result<()> {
+    assert_eq!(
+        day10::main(&["data/day10.txt".to_owned()])?,
+        (210, Some(802))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_day11() -> Result<()> {
+    assert_eq!(
+        day11::main(&["data/day11.txt".to_owned()])?,
+        (6, Some(["  #", "  #", "## "].join("\n")))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_day12() -> Result<()> {
+    assert_eq!(
+        day12::main(&["data/day12.txt".to_owned()])?,
+        (14645, Some(4686774924))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_day13() -> Result<()> {
+    assert_eq!(
+        day13::main(&["data/day13.txt".to_owned()])?,
+        (3, Some(3000))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_day14() -> Result<()> {
+    assert_eq!(
+        day14::main(&["data/day14.txt".to_owned()])?,
+        (13312, Some(82892753))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_day15() -> Result<()> {
+    assert_eq!(day15::main(&["data/day15.txt".to_owned()])?, (32, Some(36)));
+    Ok(())
+}
+
+#[test]
+fn test_day16() -> Result<()> {
+    assert_eq!(
+        day16::main(&["data/day16.txt".to_owned()])?,
+        ("24465799".to_owned(), Some("84462026".to_owned()))
+    );
+    Ok(())
+}